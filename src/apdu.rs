@@ -0,0 +1,205 @@
+//! ISO/IEC 7816-4 command and response APDUs exchanged over ISO-DEP.
+
+/// Longest command data field `AS3910::exchange_apdu` sends
+pub const MAX_DATA_LEN: usize = 512;
+
+/// Longest encoded command APDU: header, extended Lc, data and extended Le
+pub const MAX_COMMAND_LEN: usize = 4 + 3 + MAX_DATA_LEN + 2;
+
+/// Largest number of response bytes a command can ask for with Le
+pub const MAX_LE: usize = 65536;
+
+/// Status word of a successful command
+pub const SW_SUCCESS: u16 = 0x9000;
+
+/// Longest application identifier
+pub const MAX_AID_LEN: usize = 16;
+
+/// Instruction byte of SELECT
+pub const INS_SELECT: u8 = 0xA4;
+
+/// Command APDU.
+///
+/// Short length fields are used when the data is at most 255 bytes and Le at most 256,
+/// extended length fields otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Apdu<'a> {
+    pub cla: u8,
+    pub ins: u8,
+    pub p1: u8,
+    pub p2: u8,
+    /// Command data, Lc is derived from its length
+    pub data: &'a [u8],
+    /// Expected response length, `None` when no response data is expected
+    pub le: Option<usize>,
+}
+
+impl<'a> Apdu<'a> {
+    pub fn new(cla: u8, ins: u8, p1: u8, p2: u8) -> Self {
+        Apdu {
+            cla,
+            ins,
+            p1,
+            p2,
+            data: &[],
+            le: None,
+        }
+    }
+
+    pub fn data(mut self, data: &'a [u8]) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Expects up to `le` response bytes, `MAX_LE` or 256 in short APDUs ask for as many as available
+    pub fn le(mut self, le: usize) -> Self {
+        self.le = Some(le);
+        self
+    }
+
+    /// Whether the APDU needs extended length fields
+    pub fn is_extended(&self) -> bool {
+        self.data.len() > 255 || self.le.is_some_and(|le| le > 256)
+    }
+
+    /// Writes the encoded APDU to `buffer` and returns its length. `None` when it doesn't fit,
+    /// the data is longer than 65535 bytes or Le is 0 or larger than `MAX_LE`
+    pub fn encode(&self, buffer: &mut [u8]) -> Option<usize> {
+        if self.data.len() > u16::MAX as usize || self.le.is_some_and(|le| le == 0 || le > MAX_LE) {
+            return None;
+        }
+        let extended = self.is_extended();
+        let mut len = 0;
+        let mut push = |bytes: &[u8]| -> Option<()> {
+            buffer.get_mut(len..len + bytes.len())?.copy_from_slice(bytes);
+            len += bytes.len();
+            Some(())
+        };
+        push(&[self.cla, self.ins, self.p1, self.p2])?;
+        let lc = self.data.len() as u16;
+        if !self.data.is_empty() {
+            if extended {
+                let [high, low] = lc.to_be_bytes();
+                push(&[0, high, low])?;
+            } else {
+                push(&[lc as u8])?;
+            }
+            push(self.data)?;
+        }
+        if let Some(le) = self.le {
+            // The largest Le is encoded as 0
+            let [high, low] = (le as u16).to_be_bytes();
+            if !extended {
+                push(&[low])?;
+            } else if self.data.is_empty() {
+                push(&[0, high, low])?;
+            } else {
+                push(&[high, low])?;
+            }
+        }
+        Some(len)
+    }
+}
+
+/// Response APDU, the response data followed by the status bytes SW1 and SW2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ApduResponse<'a> {
+    pub data: &'a [u8],
+    pub sw1: u8,
+    pub sw2: u8,
+}
+
+impl<'a> ApduResponse<'a> {
+    /// Splits a received response into data and status, `None` when it is shorter than the status
+    pub fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let (data, &[sw1, sw2]) = bytes.split_last_chunk::<2>()?;
+        Some(ApduResponse { data, sw1, sw2 })
+    }
+
+    /// SW1 and SW2 as one status word
+    pub fn sw(&self) -> u16 {
+        u16::from_be_bytes([self.sw1, self.sw2])
+    }
+
+    /// Whether the command completed normally with `SW_SUCCESS`
+    pub fn is_success(&self) -> bool {
+        self.sw() == SW_SUCCESS
+    }
+}
+
+/// File Control Information returned by SELECT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Fci<'a> {
+    /// Complete response data
+    pub raw: &'a [u8],
+    /// DF name (tag 84), the AID of the selected application
+    pub df_name: Option<&'a [u8]>,
+    /// FCI proprietary template (tag A5), e.g. the EMV application label and PDOL
+    pub proprietary: Option<&'a [u8]>,
+}
+
+impl<'a> Fci<'a> {
+    const TEMPLATE: u16 = 0x6F;
+    const DF_NAME: u16 = 0x84;
+    const PROPRIETARY: u16 = 0xA5;
+
+    /// Extracts the FCI template (tag 6F) from SELECT response data. Cards which answer
+    /// without FCI or with another template only fill `raw`.
+    pub fn parse(raw: &'a [u8]) -> Self {
+        let mut fci = Fci {
+            raw,
+            df_name: None,
+            proprietary: None,
+        };
+        if let Some((Self::TEMPLATE, template, _)) = read_tlv(raw) {
+            let mut rest = template;
+            while let Some((tag, value, tail)) = read_tlv(rest) {
+                match tag {
+                    Self::DF_NAME => fci.df_name = Some(value),
+                    Self::PROPRIETARY => fci.proprietary = Some(value),
+                    _ => {}
+                }
+                rest = tail;
+            }
+        }
+        fci
+    }
+}
+
+/// Splits the first BER-TLV data object off `bytes`, returns its tag, value and the rest.
+/// Tags longer than two bytes and lengths above 65535 are not supported
+fn read_tlv(bytes: &[u8]) -> Option<(u16, &[u8], &[u8])> {
+    let (&first, mut rest) = bytes.split_first()?;
+    let mut tag = first as u16;
+    // All tag number bits set: the tag number continues in the next byte
+    if first & 0x1F == 0x1F {
+        let (&second, tail) = rest.split_first()?;
+        if second & 0x80 != 0 {
+            return None;
+        }
+        tag = tag << 8 | second as u16;
+        rest = tail;
+    }
+    let (&len, tail) = rest.split_first()?;
+    rest = tail;
+    let len = match len {
+        0x00..=0x7F => len as usize,
+        0x81 => {
+            let (&len, tail) = rest.split_first()?;
+            rest = tail;
+            len as usize
+        }
+        0x82 => {
+            let (&[high, low], tail) = rest.split_first_chunk::<2>()?;
+            rest = tail;
+            u16::from_be_bytes([high, low]) as usize
+        }
+        _ => return None,
+    };
+    let value = rest.get(..len)?;
+    let tail = rest.get(len..)?;
+    Some((tag, value, tail))
+}
@@ -0,0 +1,136 @@
+//! Results of the AS3910 calibration commands.
+
+use crate::config::RegulatorVoltage;
+use crate::register::AntennaCalibrationFlags;
+
+/// Result of the Calibrate antenna command, decoded from the Antenna Calibration Register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AntennaCalibrationResult {
+    /// State of the 4 trim switches (TRIM1_x/TRIM2_x) when resonance was reached
+    pub trim: u8,
+    /// Calibrate antenna sequence was not able to adjust resonance
+    pub error: bool,
+    /// Raw Antenna Calibration Register value
+    pub raw: u8,
+}
+
+impl From<u8> for AntennaCalibrationResult {
+    fn from(raw: u8) -> Self {
+        let flags = AntennaCalibrationFlags::from_bits_truncate(raw);
+        Self {
+            trim: (flags & AntennaCalibrationFlags::TRI).bits() >> 4,
+            error: flags.contains(AntennaCalibrationFlags::TRI_ERR),
+            raw,
+        }
+    }
+}
+
+/// Deviation in A/D converter LSB above which `AntennaResonance::needs_recalibration` suggests
+/// running Calibrate antenna again. 8 LSB is about 3.7°, 1% of the carrier period.
+pub const RESONANCE_TOLERANCE: u8 = 8;
+
+/// Result of the Check antenna resonance command.
+///
+/// The phase detector output is converted in relative mode, perfect tuning (90° phase shift)
+/// results in the middle of the range (0x7F or 0x80). One LSB is 0.13% of the carrier period (0.468°).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AntennaResonance {
+    /// Raw A/D Converter Output Register value
+    pub raw: u8,
+}
+
+impl AntennaResonance {
+    /// Signed deviation from perfect tuning in LSB. Positive when the resonance frequency
+    /// is higher than 13.56 MHz, negative when lower.
+    pub fn offset(&self) -> i16 {
+        if self.raw >= 0x80 {
+            self.raw as i16 - 0x80
+        } else {
+            self.raw as i16 - 0x7F
+        }
+    }
+
+    /// Phase deviation from 90° in thousandths of a degree
+    pub fn phase_offset_millidegrees(&self) -> i32 {
+        self.offset() as i32 * 468
+    }
+
+    /// Whether the deviation is larger than `tolerance` LSB
+    pub fn is_detuned(&self, tolerance: u8) -> bool {
+        self.offset().unsigned_abs() > tolerance as u16
+    }
+
+    /// Whether the deviation is larger than `RESONANCE_TOLERANCE`
+    pub fn needs_recalibration(&self) -> bool {
+        self.is_detuned(RESONANCE_TOLERANCE)
+    }
+}
+
+/// Result of the Calibrate Modulation Depth command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ModulationDepthResult {
+    /// Modulation Depth Display Register. Each set bit disables one of the 8 binary
+    /// weighted antenna driver segments during AM modulated state
+    pub disabled_segments: u8,
+    /// Non-modulated carrier amplitude measured at the start of the calibration
+    pub carrier_level: u8,
+}
+
+/// Calibration state of a reader, captured after the calibration commands were run once
+/// (e.g. at the factory) and restored at boot without running them again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CalibrationProfile {
+    /// Antenna trim switches code, forced through the External Trim Register on restore
+    pub trim: u8,
+    /// Antenna driver segments disabled in AM modulated state, restored as manual modulation depth
+    pub am_modulated_level: u8,
+    /// Antenna driver segments disabled in non-modulated state
+    pub non_modulated_level: u8,
+    /// Regulated voltage, restored as external definition
+    pub regulated_voltage: RegulatorVoltage,
+}
+
+impl CalibrationProfile {
+    /// Size of the serialized profile
+    pub const SIZE: usize = 4;
+
+    /// Serializes the profile, e.g. to store it in MCU flash
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        [
+            self.trim,
+            self.am_modulated_level,
+            self.non_modulated_level,
+            self.regulated_voltage as u8,
+        ]
+    }
+
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        Self {
+            trim: bytes[0] & 0b1111,
+            am_modulated_level: bytes[1],
+            non_modulated_level: bytes[2],
+            regulated_voltage: RegulatorVoltage::from_code(bytes[3]),
+        }
+    }
+}
+
+/// What `AS3910::maintenance` found and changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaintenanceReport {
+    /// Antenna resonance before any recalibration
+    pub resonance: AntennaResonance,
+    /// Result of Calibrate antenna when the resonance was out of tolerance
+    pub antenna_recalibrated: Option<AntennaCalibrationResult>,
+    /// New regulated voltage when Adjust regulators changed it
+    pub regulators_adjusted: Option<RegulatorVoltage>,
+}
+
+impl MaintenanceReport {
+    /// Whether any setting was changed
+    pub fn changed(&self) -> bool {
+        self.antenna_recalibrated.is_some() || self.regulators_adjusted.is_some()
+    }
+}
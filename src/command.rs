@@ -58,4 +58,34 @@ impl Command {
     pub fn command_pattern(&self) -> u8 {
         (*self as u8) | C
     }
+
+    /// Whether the command sends a frame
+    pub fn is_transmit(&self) -> bool {
+        matches!(
+            self,
+            Command::TransmitWithCRC
+                | Command::TransmitWithoutCRC
+                | Command::TransmitREQA
+                | Command::TransmitWUPA
+                | Command::NFCTransmitWithInitialRFCollisionAvoidance
+                | Command::NFCTransmitWithResponseRFCollisionAvoidance
+                | Command::NFCTransmitWithResponseRFCollisionAvoidanceWithN0
+        )
+    }
+
+    /// Worst case execution time of the commands which do not signal completion
+    /// with an interrupt, rounded up to whole milliseconds. 0 for all other commands
+    pub fn execution_time_ms(&self) -> u16 {
+        match self {
+            // 42us
+            Command::ADConvert | Command::MeasureRF | Command::CheckAntennaResonance => 1,
+            // 500us
+            Command::Squelch => 1,
+            Command::AdjustRegulators => 5,
+            Command::CalibrateModulationDepth => 10,
+            // 400us
+            Command::CalibrateAntenna => 1,
+            _ => 0,
+        }
+    }
 }
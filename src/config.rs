@@ -0,0 +1,1156 @@
+//! Typed configuration of the AS3910 registers.
+
+use crate::register::{
+    Configuration3Flags, Configuration4Flags, Configuration5Flags, ExternalTrimFlags,
+    ModeDefinitionFlags, ModulationDepthDefinitionFlags, NfcipFieldDetectionThresholdFlags,
+    OperationControlFlags, ReceiverConfigurationFlags, Register, RegulatedVoltageDefinitionFlags,
+};
+use crate::timing;
+use crate::Error;
+
+/// Typed view of a configuration register, written with `AS3910::apply`
+/// and read back with `AS3910::read_config`.
+pub trait RegisterConfig: Sized {
+    /// Register holding the configuration
+    const REGISTER: Register;
+
+    fn from_bits(bits: u8) -> Self;
+
+    fn bits(&self) -> u8;
+}
+
+/// Operation Control Register configuration (power, receiver and transmitter enables)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OperationControlConfig {
+    /// Enables oscillator and regulators (Ready mode). Internally ORed with the EN pin
+    pub oscillator_and_regulators: bool,
+    /// Enables receiver operation
+    pub receiver: bool,
+    /// Low power receiver operation, reduces consumption at the cost of sensitivity
+    pub receiver_low_power: bool,
+    /// Enables RF output (field)
+    pub transmitter: bool,
+    /// Enables Initial NFC Target mode, only allowed in NFC mode
+    pub nfc_target: bool,
+}
+
+impl OperationControlConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn oscillator_and_regulators(mut self, enable: bool) -> Self {
+        self.oscillator_and_regulators = enable;
+        self
+    }
+
+    pub fn receiver(mut self, enable: bool) -> Self {
+        self.receiver = enable;
+        self
+    }
+
+    pub fn receiver_low_power(mut self, enable: bool) -> Self {
+        self.receiver_low_power = enable;
+        self
+    }
+
+    pub fn transmitter(mut self, enable: bool) -> Self {
+        self.transmitter = enable;
+        self
+    }
+
+    pub fn nfc_target(mut self, enable: bool) -> Self {
+        self.nfc_target = enable;
+        self
+    }
+}
+
+impl RegisterConfig for OperationControlConfig {
+    const REGISTER: Register = Register::OperationControl;
+
+    fn from_bits(bits: u8) -> Self {
+        let flags = OperationControlFlags::from_bits_truncate(bits);
+        Self {
+            oscillator_and_regulators: flags.contains(OperationControlFlags::EN),
+            receiver: flags.contains(OperationControlFlags::RX_EN),
+            receiver_low_power: flags.contains(OperationControlFlags::RX_LP),
+            transmitter: flags.contains(OperationControlFlags::TX_EN),
+            nfc_target: flags.contains(OperationControlFlags::NFC_T),
+        }
+    }
+
+    fn bits(&self) -> u8 {
+        let mut flags = OperationControlFlags::empty();
+        flags.set(OperationControlFlags::EN, self.oscillator_and_regulators);
+        flags.set(OperationControlFlags::RX_EN, self.receiver);
+        flags.set(OperationControlFlags::RX_LP, self.receiver_low_power);
+        flags.set(OperationControlFlags::TX_EN, self.transmitter);
+        flags.set(OperationControlFlags::NFC_T, self.nfc_target);
+        flags.bits()
+    }
+}
+
+/// AGC operating mode
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AgcMode {
+    /// AGC operates during complete receive period
+    #[default]
+    CompleteReceivePeriod,
+    /// AGC operates on first eight subcarrier pulses
+    FirstEightPulses,
+}
+
+/// Receiver filter selection. Automatically preset by the chip when ISO mode or
+/// receive data rate changes, can be overridden afterwards.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiverFilter {
+    #[default]
+    Iso14443a106,
+    Iso14443b106,
+    Iso14443_212,
+    Iso14443_424,
+    Iso14443_848,
+    /// 424/484 kHz subcarriers
+    Subcarrier424,
+    /// 212 kHz subcarrier
+    Subcarrier212,
+    /// Combination used for block testing purposes
+    Other(u8),
+}
+
+impl ReceiverFilter {
+    fn bits(&self) -> u8 {
+        match self {
+            ReceiverFilter::Iso14443a106 => 0b000,
+            ReceiverFilter::Iso14443b106 => 0b001,
+            ReceiverFilter::Iso14443_212 => 0b010,
+            ReceiverFilter::Iso14443_424 => 0b011,
+            ReceiverFilter::Iso14443_848 => 0b100,
+            ReceiverFilter::Subcarrier424 => 0b110,
+            ReceiverFilter::Subcarrier212 => 0b111,
+            ReceiverFilter::Other(bits) => bits & 0b111,
+        }
+    }
+
+    fn from_bits(bits: u8) -> Self {
+        match bits & 0b111 {
+            0b000 => ReceiverFilter::Iso14443a106,
+            0b001 => ReceiverFilter::Iso14443b106,
+            0b010 => ReceiverFilter::Iso14443_212,
+            0b011 => ReceiverFilter::Iso14443_424,
+            0b100 => ReceiverFilter::Iso14443_848,
+            0b110 => ReceiverFilter::Subcarrier424,
+            0b111 => ReceiverFilter::Subcarrier212,
+            bits => ReceiverFilter::Other(bits),
+        }
+    }
+}
+
+/// Receiver Configuration Register configuration (AGC, gain reduction and filter)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReceiverConfig {
+    /// Enables AGC. Useful in case the transponder is close to the reader
+    pub agc: bool,
+    pub agc_mode: AgcMode,
+    /// Gain reduction in 3dB steps, from 0 to 7 (21dB)
+    pub gain_reduction_steps: u8,
+    pub filter: ReceiverFilter,
+}
+
+impl Default for ReceiverConfig {
+    /// AGC enabled, no gain reduction, ISO-14443A 106 kb/s filter
+    fn default() -> Self {
+        Self {
+            agc: true,
+            agc_mode: AgcMode::default(),
+            gain_reduction_steps: 0,
+            filter: ReceiverFilter::default(),
+        }
+    }
+}
+
+impl ReceiverConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn agc(mut self, enable: bool) -> Self {
+        self.agc = enable;
+        self
+    }
+
+    pub fn agc_mode(mut self, mode: AgcMode) -> Self {
+        self.agc_mode = mode;
+        self
+    }
+
+    /// Saturates at 7 steps (21dB)
+    pub fn gain_reduction_steps(mut self, steps: u8) -> Self {
+        self.gain_reduction_steps = steps.min(7);
+        self
+    }
+
+    pub fn filter(mut self, filter: ReceiverFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+impl RegisterConfig for ReceiverConfig {
+    const REGISTER: Register = Register::ReceiverConfiguration;
+
+    fn from_bits(bits: u8) -> Self {
+        let flags = ReceiverConfigurationFlags::from_bits_truncate(bits);
+        Self {
+            agc: flags.contains(ReceiverConfigurationFlags::AGC_EN),
+            agc_mode: if flags.contains(ReceiverConfigurationFlags::AGC_M) {
+                AgcMode::FirstEightPulses
+            } else {
+                AgcMode::CompleteReceivePeriod
+            },
+            gain_reduction_steps: (bits & ReceiverConfigurationFlags::RG.bits()) >> 3,
+            filter: ReceiverFilter::from_bits(bits),
+        }
+    }
+
+    fn bits(&self) -> u8 {
+        let mut flags = ReceiverConfigurationFlags::empty();
+        flags.set(ReceiverConfigurationFlags::AGC_EN, self.agc);
+        flags.set(ReceiverConfigurationFlags::AGC_M, self.agc_mode == AgcMode::FirstEightPulses);
+        flags.bits() | (self.gain_reduction_steps.min(7) << 3) | self.filter.bits()
+    }
+}
+
+/// Demodulation type of the receiver
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Demodulation {
+    #[default]
+    Am,
+    /// Helps in so called communication holes where the signal is only PM modulated
+    Pm,
+}
+
+/// Number of received bytes in the FIFO which triggers the water level interrupt
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReceiveWaterLevel {
+    /// More margin before overflow at the cost of more frequent interrupts
+    Bytes24,
+    #[default]
+    Bytes28,
+}
+
+/// Number of bytes left to transmit in the FIFO which triggers the water level interrupt
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TransmitWaterLevel {
+    #[default]
+    Bytes4,
+    /// More margin before underflow at the cost of more frequent interrupts
+    Bytes8,
+}
+
+/// FIFO water levels, stored in Configuration Register 5 next to the demodulation settings
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FifoWaterLevel {
+    pub receive: ReceiveWaterLevel,
+    pub transmit: TransmitWaterLevel,
+}
+
+impl FifoWaterLevel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn receive(mut self, level: ReceiveWaterLevel) -> Self {
+        self.receive = level;
+        self
+    }
+
+    pub fn transmit(mut self, level: TransmitWaterLevel) -> Self {
+        self.transmit = level;
+        self
+    }
+
+    /// Decodes the water levels from a Configuration Register 5 value
+    pub fn from_bits(bits: u8) -> Self {
+        let flags = Configuration5Flags::from_bits_truncate(bits);
+        Self {
+            receive: if flags.contains(Configuration5Flags::FIFO_LR) {
+                ReceiveWaterLevel::Bytes24
+            } else {
+                ReceiveWaterLevel::Bytes28
+            },
+            transmit: if flags.contains(Configuration5Flags::FIFO_LT) {
+                TransmitWaterLevel::Bytes8
+            } else {
+                TransmitWaterLevel::Bytes4
+            },
+        }
+    }
+
+    /// Replaces the water level bits of a Configuration Register 5 value
+    pub fn update_bits(&self, bits: u8) -> u8 {
+        let mut flags = Configuration5Flags::from_bits_truncate(bits);
+        flags.set(Configuration5Flags::FIFO_LR, self.receive == ReceiveWaterLevel::Bytes24);
+        flags.set(Configuration5Flags::FIFO_LT, self.transmit == TransmitWaterLevel::Bytes8);
+        flags.bits()
+    }
+}
+
+/// Regulated voltage levels, in 100mV steps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RegulatorVoltage {
+    V2_4 = 0b0101,
+    V2_5 = 0b0110,
+    V2_6 = 0b0111,
+    V2_7 = 0b1000,
+    V2_8 = 0b1001,
+    V2_9 = 0b1010,
+    V3_0 = 0b1011,
+    V3_1 = 0b1100,
+    V3_2 = 0b1101,
+    V3_3 = 0b1110,
+    V3_4 = 0b1111,
+}
+
+impl RegulatorVoltage {
+    /// Decodes the 4 bit regulator code, all codes below 0b0101 are 2.4V
+    pub fn from_code(code: u8) -> Self {
+        match code & 0b1111 {
+            0b0110 => RegulatorVoltage::V2_5,
+            0b0111 => RegulatorVoltage::V2_6,
+            0b1000 => RegulatorVoltage::V2_7,
+            0b1001 => RegulatorVoltage::V2_8,
+            0b1010 => RegulatorVoltage::V2_9,
+            0b1011 => RegulatorVoltage::V3_0,
+            0b1100 => RegulatorVoltage::V3_1,
+            0b1101 => RegulatorVoltage::V3_2,
+            0b1110 => RegulatorVoltage::V3_3,
+            0b1111 => RegulatorVoltage::V3_4,
+            _ => RegulatorVoltage::V2_4,
+        }
+    }
+
+    pub fn millivolts(&self) -> u16 {
+        2400 + (*self as u16 - RegulatorVoltage::V2_4 as u16) * 100
+    }
+}
+
+/// Regulated Voltage Definition Register configuration.
+///
+/// The regulators need roughly 250mV of headroom below VDD, so with a 3.3V supply
+/// the regulated voltage has to be at most 3.0V. With a 5V supply the maximum
+/// 3.4V can be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegulatedVoltage {
+    /// Regulated voltage is defined by the result of Adjust regulators command
+    Automatic,
+    /// Regulated voltage is defined explicitly
+    External(RegulatorVoltage),
+}
+
+impl Default for RegulatedVoltage {
+    /// External 2.4V, the lowest level which works with any supply voltage
+    fn default() -> Self {
+        RegulatedVoltage::External(RegulatorVoltage::V2_4)
+    }
+}
+
+impl RegisterConfig for RegulatedVoltage {
+    const REGISTER: Register = Register::RegulatedVoltageDefinition;
+
+    fn from_bits(bits: u8) -> Self {
+        let flags = RegulatedVoltageDefinitionFlags::from_bits_truncate(bits);
+        if flags.contains(RegulatedVoltageDefinitionFlags::REG_S) {
+            RegulatedVoltage::External(RegulatorVoltage::from_code(
+                (flags & RegulatedVoltageDefinitionFlags::REGE).bits() >> 3,
+            ))
+        } else {
+            RegulatedVoltage::Automatic
+        }
+    }
+
+    fn bits(&self) -> u8 {
+        match self {
+            RegulatedVoltage::Automatic => 0,
+            RegulatedVoltage::External(voltage) => {
+                RegulatedVoltageDefinitionFlags::REG_S.bits() | ((*voltage as u8) << 3)
+            }
+        }
+    }
+}
+
+/// Driver configuration used during initialization
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    pub regulated_voltage: RegulatedVoltage,
+    /// With `AntennaTrim::Calibrated` the Calibrate antenna command is run during initialization,
+    /// `AntennaTrim::Manual` skips it and forces the given trim code
+    pub antenna_trim: AntennaTrim,
+    /// Read back every written register and fail with `Error::VerifyFailed` on mismatch.
+    /// Helps to detect corrupted writes on long or noisy SPI lines
+    pub verify_writes: bool,
+    /// Keep a shadow copy of every written register, returned by `AS3910::cached`
+    /// and `RegisterDump::cached` without SPI traffic
+    pub cache_registers: bool,
+    /// Re-run Squelch after every receive so receiver gain follows the ambient noise level.
+    /// Improves reliability in electrically noisy installations
+    pub auto_squelch: bool,
+    /// Masks receive data for this many microseconds after the end of transmission, 0 disables it.
+    /// Suppresses the echo of the own transmission on poorly shielded antennas. Limited to
+    /// `timing::MAX_RECEIVE_MASK_WINDOW_US` so the PICC response isn't masked as well. The window
+    /// starts when the driver sees the end of transmission, the interrupt latency adds to it
+    pub receive_mask_window_us: u16,
+    /// Measure the supply before every transceive and fail with `Error::SupplyLow` below this
+    /// many millivolts, 0 disables it. Costs an Adjust regulators run (up to 5ms) per transceive
+    pub min_supply_mv: u16,
+    /// Closed-loop adjustment of the non-modulated antenna driver level from the RSSI
+    /// of every reception, `None` keeps the configured level
+    pub adaptive_power: Option<AdaptivePower>,
+    /// Largest SPI write issued when loading the FIFO, for DMA engines with a limited transfer size.
+    /// 0 sends opcode and payload in a single write
+    pub spi_chunk_size: usize,
+    /// Response timeouts of the ISO-14443A operations
+    pub timeouts: Timeouts,
+    /// How a pending interrupt is detected
+    pub interrupt_line: InterruptLine,
+    /// Recovery run automatically after a failed transceive, see `AS3910::recover`
+    pub recovery: Recovery,
+    /// Retries of `reqa`, `select` and `transceive`
+    pub retry: RetryPolicy,
+    /// Count frames and errors, see `AS3910::stats`
+    pub collect_stats: bool,
+    /// Anticollision frames sent per cascade level before `select` gives up with
+    /// `Error::AntiCollisionMaxLoopsReached`. Every collision has to resolve one more of the
+    /// 40 UID CLn and BCC bits, so a level never takes more than 41 frames and larger values
+    /// behave like 41. Lower it to fail faster on noise that looks like collisions.
+    pub anticollision_max_loops: u8,
+    /// Unmodulated carrier sent after the field is switched on before the first command,
+    /// ISO/IEC 14443-3 requires at least 5ms for PICCs to power up
+    pub field_guard_ms: u16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            regulated_voltage: RegulatedVoltage::default(),
+            antenna_trim: AntennaTrim::default(),
+            verify_writes: false,
+            cache_registers: false,
+            auto_squelch: false,
+            receive_mask_window_us: 0,
+            min_supply_mv: 0,
+            adaptive_power: None,
+            spi_chunk_size: 0,
+            timeouts: Timeouts::default(),
+            interrupt_line: InterruptLine::default(),
+            recovery: Recovery::default(),
+            retry: RetryPolicy::default(),
+            collect_stats: false,
+            anticollision_max_loops: 32,
+            field_guard_ms: 5,
+        }
+    }
+}
+
+/// Retry of failed operations with exponential backoff.
+///
+/// An operation is attempted up to `max_attempts` times as long as it fails with one of the
+/// enabled error kinds. The first retry waits `delay_ms`, every further one `backoff_factor`
+/// times longer. The default makes a single attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RetryPolicy {
+    /// Total number of attempts, 0 and 1 disable retries
+    pub max_attempts: u8,
+    /// Delay before the first retry
+    pub delay_ms: u16,
+    /// Multiplier of the delay after every retry, 1 keeps it constant
+    pub backoff_factor: u8,
+    /// Retry after `Error::Timeout` and `Error::InterruptTimeout`. `Error::NoCard` is never retried
+    pub on_timeout: bool,
+    /// Retry after `Error::Collision`
+    pub on_collision: bool,
+    /// Retry after `Error::Crc`
+    pub on_crc: bool,
+    /// Retry after `Error::Framing` and `Error::IncompleteFrame`
+    pub on_framing: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            delay_ms: 1,
+            backoff_factor: 2,
+            on_timeout: true,
+            on_collision: false,
+            on_crc: true,
+            on_framing: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_attempts(mut self, attempts: u8) -> Self {
+        self.max_attempts = attempts;
+        self
+    }
+
+    pub fn delay_ms(mut self, delay_ms: u16) -> Self {
+        self.delay_ms = delay_ms;
+        self
+    }
+
+    pub fn backoff_factor(mut self, factor: u8) -> Self {
+        self.backoff_factor = factor;
+        self
+    }
+
+    pub fn on_timeout(mut self, enable: bool) -> Self {
+        self.on_timeout = enable;
+        self
+    }
+
+    pub fn on_collision(mut self, enable: bool) -> Self {
+        self.on_collision = enable;
+        self
+    }
+
+    pub fn on_crc(mut self, enable: bool) -> Self {
+        self.on_crc = enable;
+        self
+    }
+
+    pub fn on_framing(mut self, enable: bool) -> Self {
+        self.on_framing = enable;
+        self
+    }
+
+    /// Whether an operation failing with `error` is attempted again
+    pub fn is_retryable<E, OPE>(&self, error: &Error<E, OPE>) -> bool {
+        match error {
+            Error::InterruptTimeout | Error::Timeout(_) => self.on_timeout,
+            Error::Collision(..) => self.on_collision,
+            Error::Crc(..) => self.on_crc,
+            Error::Framing(..) | Error::IncompleteFrame => self.on_framing,
+            _ => false,
+        }
+    }
+}
+
+/// Automatic recovery after a collision, CRC, framing, FIFO or timeout error
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Recovery {
+    /// Leave the chip as the failed operation left it
+    #[default]
+    Disabled,
+    /// Clear the FIFO and reset the interrupt state
+    Clear,
+    /// Additionally switch the field off and on, which resets all PICCs to IDLE
+    CycleField,
+}
+
+/// Signalling of pending interrupts on the IRQ line
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InterruptLine {
+    /// Pin is high while an unmasked interrupt is pending, as driven by the chip
+    #[default]
+    ActiveHigh,
+    /// Pin is low while an unmasked interrupt is pending, for boards with an inverting buffer
+    ActiveLow,
+    /// The pin is ignored and the Interrupt Register is polled every millisecond, for an IRQ
+    /// line shared with other devices. Costs one SPI read per poll
+    Polled,
+}
+
+/// Time to wait for the response of each kind of operation.
+///
+/// Each value is the timeout of a single interrupt wait, the receive wait of a transceive
+/// starts at the end of transmission. Defaults are derived from the ISO-14443A frame timing,
+/// see `timing`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Timeouts {
+    /// REQA and WUPA
+    pub request_ms: u16,
+    /// Anticollision and select frames
+    pub anticollision_ms: u16,
+    /// HLTA, a PICC which responds within this time does not acknowledge the halt
+    pub hlta_ms: u16,
+    /// Default of `TransceiveOptions` created with `AS3910::transceive_options`, and the shortest
+    /// block timeout of `AS3910::iso_dep_transceive`, which otherwise follows the FWI of the ATS.
+    /// `iso_dep` sets it from a frame waiting time integer
+    pub transceive_ms: u16,
+    /// Total time an ISO-DEP PICC may request with S(WTX) for a single block before
+    /// `AS3910::iso_dep_transceive` gives up with `Error::Timeout`
+    pub wtx_max_ms: u16,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            // The wait starts before the REQA/WUPA frame itself is sent
+            request_ms: timing::timeout_ms(
+                timing::frame_duration_us(1) + timing::FRAME_DELAY_US + timing::frame_duration_us(2),
+            )
+            .max(timing::MIN_RESPONSE_TIMEOUT_MS),
+            // Longest response is the SAK with CRC, or the 5 byte UID CLn
+            anticollision_ms: timing::response_timeout_ms(5),
+            hlta_ms: 1,
+            transceive_ms: timing::iso_dep_timeout_ms(timing::DEFAULT_FWI),
+            // One extension up to the longest frame waiting time, FWT(14) + ΔFWT ≈ 4953 ms. PICCs may
+            // repeat S(WTX) for as long as they need, raise it for long running commands
+            wtx_max_ms: timing::iso_dep_timeout_ms(timing::MAX_FWI),
+        }
+    }
+}
+
+impl Timeouts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn request_ms(mut self, timeout_ms: u16) -> Self {
+        self.request_ms = timeout_ms;
+        self
+    }
+
+    pub fn anticollision_ms(mut self, timeout_ms: u16) -> Self {
+        self.anticollision_ms = timeout_ms;
+        self
+    }
+
+    pub fn hlta_ms(mut self, timeout_ms: u16) -> Self {
+        self.hlta_ms = timeout_ms;
+        self
+    }
+
+    pub fn transceive_ms(mut self, timeout_ms: u16) -> Self {
+        self.transceive_ms = timeout_ms;
+        self
+    }
+
+    pub fn wtx_max_ms(mut self, timeout_ms: u16) -> Self {
+        self.wtx_max_ms = timeout_ms;
+        self
+    }
+
+    /// Sets the transceive timeout to the frame waiting time of `fwi` (from the ATS) plus ΔFWT
+    pub fn iso_dep(mut self, fwi: u8) -> Self {
+        self.transceive_ms = timing::iso_dep_timeout_ms(fwi);
+        self
+    }
+}
+
+/// Parameters of the closed-loop output power adjustment.
+///
+/// After every reception the RSSI is compared with the thresholds: above `rssi_high`
+/// (card close to the antenna) `step` more driver segment weight is disabled, below `rssi_low`
+/// (card at the edge of range) `step` is enabled again. Changes the non-modulated level,
+/// so it is meant for ISO-14443A or manual modulation depth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AdaptivePower {
+    /// RSSI (0 to 15) above which the output power is reduced
+    pub rssi_high: u8,
+    /// RSSI (0 to 15) below which the output power is increased
+    pub rssi_low: u8,
+    /// Disabled segments weight added or removed per adjustment
+    pub step: u8,
+    /// Upper bound of the disabled segments, limits the lowest output power
+    pub max_disabled_segments: u8,
+}
+
+impl Default for AdaptivePower {
+    fn default() -> Self {
+        Self {
+            rssi_high: 12,
+            rssi_low: 4,
+            step: 0x10,
+            max_disabled_segments: 0xC0,
+        }
+    }
+}
+
+impl AdaptivePower {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rssi_high(mut self, rssi: u8) -> Self {
+        self.rssi_high = rssi;
+        self
+    }
+
+    pub fn rssi_low(mut self, rssi: u8) -> Self {
+        self.rssi_low = rssi;
+        self
+    }
+
+    pub fn step(mut self, step: u8) -> Self {
+        self.step = step;
+        self
+    }
+
+    pub fn max_disabled_segments(mut self, max: u8) -> Self {
+        self.max_disabled_segments = max;
+        self
+    }
+
+    /// Returns the disabled segments to use after a reception with `rssi`
+    pub fn next_level(&self, disabled_segments: u8, rssi: u8) -> u8 {
+        if rssi > self.rssi_high {
+            disabled_segments.saturating_add(self.step).min(self.max_disabled_segments)
+        } else if rssi < self.rssi_low {
+            disabled_segments.saturating_sub(self.step)
+        } else {
+            disabled_segments
+        }
+    }
+}
+
+/// Configuration Register 3 configuration (ISO-14443A and NFC framing)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Iso14443aConfig {
+    /// Receive without CRC, needed for ISO-14443A anticollision (ATQA and UID responses)
+    pub receive_without_crc: bool,
+    /// No byte parity checking. Parity bits are still removed before received data is put in FIFO
+    pub no_parity_check: bool,
+    /// Modulation pulse reduction in number of 13.56 MHz clock periods (0 to 15)
+    pub pulse_reduction: u8,
+}
+
+impl Iso14443aConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn receive_without_crc(mut self, enable: bool) -> Self {
+        self.receive_without_crc = enable;
+        self
+    }
+
+    pub fn no_parity_check(mut self, enable: bool) -> Self {
+        self.no_parity_check = enable;
+        self
+    }
+
+    /// Saturates at 15 clock periods
+    pub fn pulse_reduction(mut self, clock_periods: u8) -> Self {
+        self.pulse_reduction = clock_periods.min(15);
+        self
+    }
+}
+
+impl RegisterConfig for Iso14443aConfig {
+    const REGISTER: Register = Register::ConfigurationRegister3;
+
+    fn from_bits(bits: u8) -> Self {
+        let flags = Configuration3Flags::from_bits_truncate(bits);
+        Self {
+            receive_without_crc: flags.contains(Configuration3Flags::CRC_RX),
+            no_parity_check: flags.contains(Configuration3Flags::NO_PAR),
+            pulse_reduction: (flags & Configuration3Flags::P_LEN).bits() >> 2,
+        }
+    }
+
+    fn bits(&self) -> u8 {
+        let mut flags = Configuration3Flags::empty();
+        flags.set(Configuration3Flags::CRC_RX, self.receive_without_crc);
+        flags.set(Configuration3Flags::NO_PAR, self.no_parity_check);
+        flags.bits() | (self.pulse_reduction.min(15) << 2)
+    }
+}
+
+/// Configuration Register 4 configuration (ISO-14443B framing).
+///
+/// CRC is selected per transmit with `TransmitWithCRC`/`TransmitWithoutCRC` commands.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Iso14443bConfig {
+    /// Extra guard time between characters in number of etu (0 to 6)
+    pub egt_etu: u8,
+    /// SOF with 11 etu of logic 0, otherwise 10 etu
+    pub long_sof_low: bool,
+    /// SOF with 3 etu of logic 1, otherwise 2 etu
+    pub long_sof_high: bool,
+    /// EOF with 11 etu of logic 0, otherwise 10 etu
+    pub long_eof: bool,
+    /// EGT after each character, otherwise no EGT after last character
+    pub egt_after_each_character: bool,
+}
+
+impl Iso14443bConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Saturates at 6 etu
+    pub fn egt_etu(mut self, etu: u8) -> Self {
+        self.egt_etu = etu.min(6);
+        self
+    }
+
+    pub fn long_sof_low(mut self, enable: bool) -> Self {
+        self.long_sof_low = enable;
+        self
+    }
+
+    pub fn long_sof_high(mut self, enable: bool) -> Self {
+        self.long_sof_high = enable;
+        self
+    }
+
+    pub fn long_eof(mut self, enable: bool) -> Self {
+        self.long_eof = enable;
+        self
+    }
+
+    pub fn egt_after_each_character(mut self, enable: bool) -> Self {
+        self.egt_after_each_character = enable;
+        self
+    }
+}
+
+impl RegisterConfig for Iso14443bConfig {
+    const REGISTER: Register = Register::ConfigurationRegister4;
+
+    fn from_bits(bits: u8) -> Self {
+        let flags = Configuration4Flags::from_bits_truncate(bits);
+        Self {
+            egt_etu: ((flags & Configuration4Flags::EGT_LEN).bits() >> 5).min(6),
+            long_sof_low: flags.contains(Configuration4Flags::SOF_0),
+            long_sof_high: flags.contains(Configuration4Flags::SOF_1),
+            long_eof: flags.contains(Configuration4Flags::EOF),
+            egt_after_each_character: flags.contains(Configuration4Flags::EGT),
+        }
+    }
+
+    fn bits(&self) -> u8 {
+        let mut flags = Configuration4Flags::empty();
+        flags.set(Configuration4Flags::SOF_0, self.long_sof_low);
+        flags.set(Configuration4Flags::SOF_1, self.long_sof_high);
+        flags.set(Configuration4Flags::EOF, self.long_eof);
+        flags.set(Configuration4Flags::EGT, self.egt_after_each_character);
+        flags.bits() | (self.egt_etu.min(6) << 5)
+    }
+}
+
+/// ISO-14443 data rate
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum BitRate {
+    #[default]
+    Kbps106 = 0b000,
+    Kbps212 = 0b001,
+    Kbps424 = 0b010,
+    Kbps848 = 0b011,
+}
+
+impl BitRate {
+    fn from_bits(bits: u8) -> Self {
+        match bits & 0b111 {
+            0b001 => BitRate::Kbps212,
+            0b010 => BitRate::Kbps424,
+            0b011 => BitRate::Kbps848,
+            _ => BitRate::Kbps106,
+        }
+    }
+}
+
+/// Protocol mode selected in the ISO Mode Definition Register.
+///
+/// NFCIP-1 passive communication and bit rates above 106 kbps in NFC mode are not
+/// supported by the AS3910. Initiator or target role is selected by the transmit
+/// commands and `OperationControlConfig::nfc_target`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperatingMode {
+    Iso14443a { tx: BitRate, rx: BitRate },
+    Iso14443b { tx: BitRate, rx: BitRate },
+    /// NFCIP-1 106 kbps active communication mode
+    Nfcip1Active,
+}
+
+impl Default for OperatingMode {
+    /// ISO-14443A at 106 kbps, the state after power up and Set Default command
+    fn default() -> Self {
+        OperatingMode::Iso14443a {
+            tx: BitRate::Kbps106,
+            rx: BitRate::Kbps106,
+        }
+    }
+}
+
+impl OperatingMode {
+    /// Receiver filter the chip presets when this mode is selected
+    pub fn receiver_filter(&self) -> ReceiverFilter {
+        match self {
+            OperatingMode::Iso14443a { rx: BitRate::Kbps106, .. } | OperatingMode::Nfcip1Active => {
+                ReceiverFilter::Iso14443a106
+            }
+            OperatingMode::Iso14443b { rx: BitRate::Kbps106, .. } => ReceiverFilter::Iso14443b106,
+            OperatingMode::Iso14443a { rx, .. } | OperatingMode::Iso14443b { rx, .. } => match rx {
+                BitRate::Kbps212 => ReceiverFilter::Iso14443_212,
+                BitRate::Kbps424 => ReceiverFilter::Iso14443_424,
+                _ => ReceiverFilter::Iso14443_848,
+            },
+        }
+    }
+}
+
+impl RegisterConfig for OperatingMode {
+    const REGISTER: Register = Register::ModeDefinition;
+
+    fn from_bits(bits: u8) -> Self {
+        let flags = ModeDefinitionFlags::from_bits_truncate(bits);
+        let tx = BitRate::from_bits((flags & ModeDefinitionFlags::TX_RATE).bits() >> 3);
+        let rx = BitRate::from_bits((flags & ModeDefinitionFlags::RX_RATE).bits());
+        if flags.contains(ModeDefinitionFlags::NFC) {
+            OperatingMode::Nfcip1Active
+        } else if flags.contains(ModeDefinitionFlags::ISO14443B) {
+            OperatingMode::Iso14443b { tx, rx }
+        } else {
+            OperatingMode::Iso14443a { tx, rx }
+        }
+    }
+
+    fn bits(&self) -> u8 {
+        match self {
+            OperatingMode::Iso14443a { tx, rx } => ((*tx as u8) << 3) | *rx as u8,
+            OperatingMode::Iso14443b { tx, rx } => {
+                ModeDefinitionFlags::ISO14443B.bits() | ((*tx as u8) << 3) | *rx as u8
+            }
+            OperatingMode::Nfcip1Active => ModeDefinitionFlags::NFC.bits(),
+        }
+    }
+}
+
+/// Modulation Depth Definition Register configuration (AM modulation, ISO-14443B and transparent mode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModulationDepthConfig {
+    /// AM modulated level is adjusted by Calibrate Modulation Depth command. `mod_bits` are the
+    /// 6 binary decimals of the non-modulated to modulated amplitude ratio a/b (1 to 1.98)
+    Calibrated { mod_bits: u8 },
+    /// AM modulated level is defined by the Antenna Driver AM Modulated Level Definition Register
+    Manual,
+}
+
+impl ModulationDepthConfig {
+    /// Target modulation index (a-b)/(a+b) in percent, saturates at 33%.
+    /// ISO-14443B requires 8% to 14%, typically 10%.
+    pub fn from_modulation_index(percent: u8) -> Self {
+        let percent = percent.min(33) as u16;
+        // a/b = (1 + m) / (1 - m), truncated to 6 binary decimals
+        let ratio = (100 + percent) * 64 / (100 - percent);
+        ModulationDepthConfig::Calibrated {
+            mod_bits: (ratio - 64).min(0b11_1111) as u8,
+        }
+    }
+}
+
+impl Default for ModulationDepthConfig {
+    fn default() -> Self {
+        ModulationDepthConfig::Calibrated { mod_bits: 0 }
+    }
+}
+
+impl RegisterConfig for ModulationDepthConfig {
+    const REGISTER: Register = Register::ModularDepthDefinition;
+
+    fn from_bits(bits: u8) -> Self {
+        let flags = ModulationDepthDefinitionFlags::from_bits_truncate(bits);
+        if flags.contains(ModulationDepthDefinitionFlags::AM_S) {
+            ModulationDepthConfig::Manual
+        } else {
+            ModulationDepthConfig::Calibrated {
+                mod_bits: (flags & ModulationDepthDefinitionFlags::MOD).bits() >> 1,
+            }
+        }
+    }
+
+    fn bits(&self) -> u8 {
+        match self {
+            ModulationDepthConfig::Calibrated { mod_bits } => (mod_bits & 0b11_1111) << 1,
+            ModulationDepthConfig::Manual => ModulationDepthDefinitionFlags::AM_S.bits(),
+        }
+    }
+}
+
+/// Antenna Driver AM Modulated Level Definition Register configuration.
+///
+/// The antenna drivers are composed of 8 binary weighted segments, each set bit disables
+/// the corresponding segment during AM modulated state. Only used with `ModulationDepthConfig::Manual`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AmModulatedLevel {
+    pub disabled_segments: u8,
+}
+
+impl RegisterConfig for AmModulatedLevel {
+    const REGISTER: Register = Register::AntennaDriverAMModulatedLevelDefinition;
+
+    fn from_bits(bits: u8) -> Self {
+        Self { disabled_segments: bits }
+    }
+
+    fn bits(&self) -> u8 {
+        self.disabled_segments
+    }
+}
+
+/// Antenna Driver Non-Modulated Level Definition Register configuration.
+///
+/// Each set bit disables the corresponding driver segment during normal non-modulated operation,
+/// reducing the field amplitude and/or antenna Q factor. Default is all segments enabled.
+/// Should not be changed after Calibrate Modulation Depth command.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NonModulatedLevel {
+    pub disabled_segments: u8,
+}
+
+impl RegisterConfig for NonModulatedLevel {
+    const REGISTER: Register = Register::AntennaDriverNonModulatedLevelDefinition;
+
+    fn from_bits(bits: u8) -> Self {
+        Self { disabled_segments: bits }
+    }
+
+    fn bits(&self) -> u8 {
+        self.disabled_segments
+    }
+}
+
+/// External Trim Register configuration, the source of the LC trim switches state
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AntennaTrim {
+    /// Trim switches are defined by the result of Calibrate antenna command
+    #[default]
+    Calibrated,
+    /// Trim switches are forced to the given 4 bit code, e.g. for factory characterized antennas
+    Manual(u8),
+}
+
+impl RegisterConfig for AntennaTrim {
+    const REGISTER: Register = Register::ExternalTrim;
+
+    fn from_bits(bits: u8) -> Self {
+        let flags = ExternalTrimFlags::from_bits_truncate(bits);
+        if flags.contains(ExternalTrimFlags::TRIM_S) {
+            AntennaTrim::Manual((flags & ExternalTrimFlags::TRE).bits() >> 3)
+        } else {
+            AntennaTrim::Calibrated
+        }
+    }
+
+    fn bits(&self) -> u8 {
+        match self {
+            AntennaTrim::Calibrated => 0,
+            AntennaTrim::Manual(code) => ExternalTrimFlags::TRIM_S.bits() | ((code & 0b1111) << 3),
+        }
+    }
+}
+
+/// Target activation thresholds in mVpp on RFI1, indexed by the 4 bit `trg_l` code.
+/// Codes x000 deactivate the detector.
+const TARGET_ACTIVATION_MVPP: [u16; 16] = [
+    0, 590, 420, 350, 300, 265, 235, 220, 0, 350, 265, 220, 190, 175, 155, 145,
+];
+
+/// Collision avoidance thresholds in mVpp on RFI1, indexed by the 4 bit `rfe_t` code.
+/// Codes x000 deactivate the detector.
+const COLLISION_AVOIDANCE_MVPP: [u16; 16] = [
+    0, 50, 67, 88, 120, 172, 240, 350, 0, 145, 185, 255, 340, 480, 700, 1080,
+];
+
+/// Returns the code whose threshold is closest to `mvpp`, skipping the deactivating codes
+fn closest_threshold_code(table: &[u16; 16], mvpp: u16) -> u8 {
+    let mut best = 1;
+    let mut best_diff = u16::MAX;
+    for (code, &threshold) in table.iter().enumerate() {
+        if threshold != 0 && threshold.abs_diff(mvpp) < best_diff {
+            best = code;
+            best_diff = threshold.abs_diff(mvpp);
+        }
+    }
+    best as u8
+}
+
+/// NFCIP Field Detection Threshold Register configuration, defines when an external field
+/// is considered present in NFC mode. Both detectors are deactivated by default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FieldDetectionThreshold {
+    /// `trg_l` code, threshold of the Target Activation Detector used in NFC target mode
+    pub target_activation: u8,
+    /// `rfe_t` code, threshold of the RF Collision Avoidance Detector
+    pub collision_avoidance: u8,
+}
+
+impl FieldDetectionThreshold {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn target_activation(mut self, code: u8) -> Self {
+        self.target_activation = code & 0b1111;
+        self
+    }
+
+    pub fn collision_avoidance(mut self, code: u8) -> Self {
+        self.collision_avoidance = code & 0b1111;
+        self
+    }
+
+    /// Selects the target activation code closest to `mvpp` (145 to 590mVpp)
+    pub fn target_activation_mvpp(self, mvpp: u16) -> Self {
+        self.target_activation(closest_threshold_code(&TARGET_ACTIVATION_MVPP, mvpp))
+    }
+
+    /// Selects the collision avoidance code closest to `mvpp` (50 to 1080mVpp)
+    pub fn collision_avoidance_mvpp(self, mvpp: u16) -> Self {
+        self.collision_avoidance(closest_threshold_code(&COLLISION_AVOIDANCE_MVPP, mvpp))
+    }
+
+    /// Target activation threshold in mVpp on RFI1, `None` when the detector is deactivated
+    pub fn target_activation_millivolts(&self) -> Option<u16> {
+        match TARGET_ACTIVATION_MVPP.get((self.target_activation & 0b1111) as usize) {
+            Some(0) | None => None,
+            Some(&mvpp) => Some(mvpp),
+        }
+    }
+
+    /// Collision avoidance threshold in mVpp on RFI1, `None` when the detector is deactivated
+    pub fn collision_avoidance_millivolts(&self) -> Option<u16> {
+        match COLLISION_AVOIDANCE_MVPP.get((self.collision_avoidance & 0b1111) as usize) {
+            Some(0) | None => None,
+            Some(&mvpp) => Some(mvpp),
+        }
+    }
+}
+
+impl RegisterConfig for FieldDetectionThreshold {
+    const REGISTER: Register = Register::NFCIPFieldDetectionThreshold;
+
+    fn from_bits(bits: u8) -> Self {
+        let flags = NfcipFieldDetectionThresholdFlags::from_bits_truncate(bits);
+        Self {
+            target_activation: (flags & NfcipFieldDetectionThresholdFlags::TRG_L).bits() >> 4,
+            collision_avoidance: (flags & NfcipFieldDetectionThresholdFlags::RFE_T).bits(),
+        }
+    }
+
+    fn bits(&self) -> u8 {
+        ((self.target_activation & 0b1111) << 4) | (self.collision_avoidance & 0b1111)
+    }
+}
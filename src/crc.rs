@@ -0,0 +1,151 @@
+//! ISO/IEC 14443 CRC computed in software, for frames sent or received with the
+//! hardware CRC disabled.
+
+/// Initial register value of CRC_A
+const CRC_A_INIT: u16 = 0x6363;
+
+/// Initial register value of CRC_B, the result is inverted
+const CRC_B_INIT: u16 = 0xFFFF;
+
+/// CRC-16/CCITT polynomial x^16 + x^12 + x^5 + 1, bit reversed for LSB first processing
+const POLYNOMIAL: u16 = 0x8408;
+
+fn update(crc: u16, data: &[u8]) -> u16 {
+    data.iter().fold(crc, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u16, |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ POLYNOMIAL
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+/// CRC_A of `data`, transmitted low byte first
+pub fn crc_a(data: &[u8]) -> u16 {
+    update(CRC_A_INIT, data)
+}
+
+/// Writes the CRC_A of the first `len` bytes of `frame` after them, returns the new length.
+/// `None` when `frame` has no room for the two CRC bytes
+pub fn append_crc_a(frame: &mut [u8], len: usize) -> Option<usize> {
+    let crc = crc_a(frame.get(..len)?);
+    frame.get_mut(len..len + 2)?.copy_from_slice(&crc.to_le_bytes());
+    Some(len + 2)
+}
+
+/// Whether the last two bytes of `frame` are the CRC_A of the bytes before them
+pub fn check_crc_a(frame: &[u8]) -> bool {
+    match frame.split_last_chunk::<2>() {
+        Some((data, crc)) => crc_a(data).to_le_bytes() == *crc,
+        None => false,
+    }
+}
+
+/// CRC_B of `data`, transmitted low byte first
+pub fn crc_b(data: &[u8]) -> u16 {
+    !update(CRC_B_INIT, data)
+}
+
+/// Writes the CRC_B of the first `len` bytes of `frame` after them, returns the new length.
+/// `None` when `frame` has no room for the two CRC bytes
+pub fn append_crc_b(frame: &mut [u8], len: usize) -> Option<usize> {
+    let crc = crc_b(frame.get(..len)?);
+    frame.get_mut(len..len + 2)?.copy_from_slice(&crc.to_le_bytes());
+    Some(len + 2)
+}
+
+/// Whether the last two bytes of `frame` are the CRC_B of the bytes before them
+pub fn check_crc_b(frame: &[u8]) -> bool {
+    match frame.split_last_chunk::<2>() {
+        Some((data, crc)) => crc_b(data).to_le_bytes() == *crc,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc_a_known_answers() {
+        // ISO/IEC 14443-3 Annex B
+        assert_eq!(crc_a(&[0x00, 0x00]).to_le_bytes(), [0xA0, 0x1E]);
+        assert_eq!(crc_a(&[0x12, 0x34]).to_le_bytes(), [0x26, 0xCF]);
+        // HLTA
+        assert_eq!(crc_a(&[0x50, 0x00]).to_le_bytes(), [0x57, 0xCD]);
+        // CRC-16/ISO-IEC-14443-3-A check value
+        assert_eq!(crc_a(b"123456789"), 0xBF05);
+        assert_eq!(crc_a(&[]), CRC_A_INIT);
+    }
+
+    #[test]
+    fn append_crc_a_writes_after_data() {
+        let mut frame = [0x50, 0x00, 0xFF, 0xFF, 0xFF];
+        assert_eq!(append_crc_a(&mut frame, 2), Some(4));
+        assert_eq!(frame, [0x50, 0x00, 0x57, 0xCD, 0xFF]);
+    }
+
+    #[test]
+    fn append_crc_a_too_short() {
+        let mut frame = [0x50, 0x00, 0x00];
+        assert_eq!(append_crc_a(&mut frame, 2), None);
+        assert_eq!(frame, [0x50, 0x00, 0x00]);
+        assert_eq!(append_crc_a(&mut [], 0), None);
+        // Data longer than the frame
+        assert_eq!(append_crc_a(&mut frame, 4), None);
+    }
+
+    #[test]
+    fn check_crc_a_frames() {
+        assert!(check_crc_a(&[0x50, 0x00, 0x57, 0xCD]));
+        assert!(check_crc_a(&[0x00, 0x00, 0xA0, 0x1E]));
+        assert!(!check_crc_a(&[0x50, 0x00, 0xCD, 0x57]));
+        assert!(!check_crc_a(&[0x51, 0x00, 0x57, 0xCD]));
+        // CRC of no data
+        assert!(check_crc_a(&[0x63, 0x63]));
+        assert!(!check_crc_a(&[0x63]));
+        assert!(!check_crc_a(&[]));
+    }
+
+    #[test]
+    fn crc_b_known_answers() {
+        // ISO/IEC 14443-3 Annex B
+        assert_eq!(crc_b(&[0x00, 0x00, 0x00]).to_le_bytes(), [0xCC, 0xC6]);
+        assert_eq!(crc_b(&[0x0F, 0xAA, 0xFF]).to_le_bytes(), [0xFC, 0xD1]);
+        assert_eq!(crc_b(&[0x0A, 0x12, 0x34, 0x56]).to_le_bytes(), [0x2C, 0xF6]);
+        // CRC-16/IBM-SDLC check value
+        assert_eq!(crc_b(b"123456789"), 0x906E);
+        assert_eq!(crc_b(&[]), 0x0000);
+    }
+
+    #[test]
+    fn append_crc_b_writes_after_data() {
+        let mut frame = [0x0A, 0x12, 0x34, 0x56, 0xFF, 0xFF, 0xFF];
+        assert_eq!(append_crc_b(&mut frame, 4), Some(6));
+        assert_eq!(frame, [0x0A, 0x12, 0x34, 0x56, 0x2C, 0xF6, 0xFF]);
+    }
+
+    #[test]
+    fn append_crc_b_too_short() {
+        let mut frame = [0x00, 0x00, 0x00, 0x00];
+        assert_eq!(append_crc_b(&mut frame, 3), None);
+        assert_eq!(frame, [0x00; 4]);
+        assert_eq!(append_crc_b(&mut [], 0), None);
+        // Data longer than the frame
+        assert_eq!(append_crc_b(&mut frame, 5), None);
+    }
+
+    #[test]
+    fn check_crc_b_frames() {
+        assert!(check_crc_b(&[0x00, 0x00, 0x00, 0xCC, 0xC6]));
+        assert!(check_crc_b(&[0x0F, 0xAA, 0xFF, 0xFC, 0xD1]));
+        assert!(!check_crc_b(&[0x00, 0x00, 0x00, 0xC6, 0xCC]));
+        // CRC_A and CRC_B of the same data differ
+        assert!(!check_crc_b(&[0x00, 0x00, 0xA0, 0x1E]));
+        assert!(check_crc_b(&[0x00, 0x00]));
+        assert!(!check_crc_b(&[0x00]));
+        assert!(!check_crc_b(&[]));
+    }
+}
@@ -0,0 +1,45 @@
+//! Lock-free delivery of interrupts from an interrupt service routine to the driver.
+//!
+//! The ISR pushes an `IrqEvent` for every edge of the IRQ line. A function installed with
+//! `AS3910::set_event_source` hands them to the driver, every interrupt wait (`transceive`,
+//! `reqa`, `wupa`, `wait_for_flags`, ...) then consumes events instead of polling the
+//! interrupt pin. With the `heapless` feature `EventQueue` provides a single producer single
+//! consumer queue for it.
+//!
+//! The source is a plain function, so the consumer half lives in a static:
+//!
+//! ```ignore
+//! static CONSUMER: Mutex<RefCell<Option<EventConsumer<'static, 8>>>> = Mutex::new(RefCell::new(None));
+//!
+//! fn next_event() -> Option<IrqEvent> {
+//!     critical_section::with(|cs| CONSUMER.borrow_ref_mut(cs).as_mut()?.dequeue())
+//! }
+//!
+//! let queue: &'static mut EventQueue<8> = cortex_m::singleton!(: EventQueue<8> = EventQueue::new()).unwrap();
+//! let (producer, consumer) = queue.split();
+//! critical_section::with(|cs| CONSUMER.borrow_ref_mut(cs).replace(consumer));
+//! // `producer` moves to the ISR, which calls `producer.enqueue(IrqEvent::Pending).ok()`
+//! as3910.set_event_source(Some(next_event));
+//! ```
+
+use crate::register::InterruptFlags;
+
+/// Interrupt reported by the ISR
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum IrqEvent {
+    /// The IRQ line was asserted, the driver reads the Interrupt Register to decode it
+    Pending,
+    /// Flags the ISR already read from the Interrupt Register
+    Flags(InterruptFlags),
+}
+
+/// Single producer single consumer queue holding up to `N - 1` events
+#[cfg(feature = "heapless")]
+pub type EventQueue<const N: usize> = heapless::spsc::Queue<IrqEvent, N>;
+/// ISR side of an `EventQueue`
+#[cfg(feature = "heapless")]
+pub type EventProducer<'a, const N: usize> = heapless::spsc::Producer<'a, IrqEvent, N>;
+/// Driver side of an `EventQueue`
+#[cfg(feature = "heapless")]
+pub type EventConsumer<'a, const N: usize> = heapless::spsc::Consumer<'a, IrqEvent, N>;
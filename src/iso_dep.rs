@@ -0,0 +1,334 @@
+//! ISO/IEC 14443-4 (ISO-DEP) activation and block protocol.
+
+use crate::config::BitRate;
+
+/// Most historical bytes kept from the ATS, further ones are dropped
+pub const MAX_HISTORICAL_BYTES: usize = 15;
+
+/// Highest card identifier, 15 is RFU
+pub const MAX_CID: u8 = 14;
+
+/// Start byte of a PPS request and response, ORed with the CID
+pub const PPSS: u8 = 0xD0;
+
+/// Highest frame size integer defined for FSDI and FSCI, higher values are RFU
+pub const MAX_FSI: u8 = 8;
+
+/// Frame size in bytes for the frame size integer FSDI or FSCI, RFU values are read as 256
+pub fn frame_size(fsi: u8) -> usize {
+    match fsi {
+        0 => 16,
+        1 => 24,
+        2 => 32,
+        3 => 40,
+        4 => 48,
+        5 => 64,
+        6 => 96,
+        7 => 128,
+        _ => 256,
+    }
+}
+
+/// Answer To Select, the PICC response to RATS.
+///
+/// Interface bytes the PICC left out hold their default values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ats {
+    /// Frame size for proximity card integer, see `frame_size`
+    pub fsci: u8,
+    /// Supported bit rates
+    pub ta: Option<u8>,
+    /// Frame waiting and start-up frame guard time integers
+    pub tb: Option<u8>,
+    /// Protocol options
+    pub tc: Option<u8>,
+    /// Frame waiting time integer, see `timing::fwt_us`
+    pub fwi: u8,
+    /// Start-up frame guard time integer
+    pub sfgi: u8,
+    historical: [u8; MAX_HISTORICAL_BYTES],
+    historical_len: u8,
+}
+
+impl Ats {
+    const TA_PRESENT: u8 = 1 << 4;
+    const TB_PRESENT: u8 = 1 << 5;
+    const TC_PRESENT: u8 = 1 << 6;
+    const TC_NAD: u8 = 1 << 0;
+    const TC_CID: u8 = 1 << 1;
+    const TA_SAME_RATE: u8 = 1 << 7;
+    /// TC when the PICC doesn't send it: CID supported, NAD not supported
+    const TC_DEFAULT: u8 = Self::TC_CID;
+
+    /// Parses an ATS received without its CRC, `None` when TL doesn't match the length.
+    /// Only the first `MAX_HISTORICAL_BYTES` historical bytes are kept.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let (&tl, rest) = bytes.split_first()?;
+        if tl as usize != bytes.len() {
+            return None;
+        }
+        let mut ats = Ats {
+            fsci: 2,
+            ta: None,
+            tb: None,
+            tc: None,
+            fwi: 4,
+            sfgi: 0,
+            historical: [0; MAX_HISTORICAL_BYTES],
+            historical_len: 0,
+        };
+        let Some((&t0, mut rest)) = rest.split_first() else {
+            return Some(ats);
+        };
+        ats.fsci = t0 & 0x0F;
+        let mut interface_byte = |present: u8| -> Option<Option<u8>> {
+            if t0 & present == 0 {
+                return Some(None);
+            }
+            let (&byte, tail) = rest.split_first()?;
+            rest = tail;
+            Some(Some(byte))
+        };
+        ats.ta = interface_byte(Self::TA_PRESENT)?;
+        ats.tb = interface_byte(Self::TB_PRESENT)?;
+        ats.tc = interface_byte(Self::TC_PRESENT)?;
+        if let Some(tb) = ats.tb {
+            ats.fwi = tb >> 4;
+            ats.sfgi = tb & 0x0F;
+        }
+        let historical = rest.get(..MAX_HISTORICAL_BYTES).unwrap_or(rest);
+        ats.historical.get_mut(..historical.len())?.copy_from_slice(historical);
+        ats.historical_len = historical.len() as u8;
+        Some(ats)
+    }
+
+    /// Largest frame the PICC accepts, in bytes including the prologue and CRC
+    pub fn fsc(&self) -> usize {
+        frame_size(self.fsci)
+    }
+
+    /// Whether the PICC accepts a CID in the block prologue
+    pub fn supports_cid(&self) -> bool {
+        self.tc.unwrap_or(Self::TC_DEFAULT) & Self::TC_CID != 0
+    }
+
+    /// Whether the PICC accepts a NAD in the block prologue
+    pub fn supports_nad(&self) -> bool {
+        self.tc.unwrap_or(Self::TC_DEFAULT) & Self::TC_NAD != 0
+    }
+
+    /// Whether the PICC accepts a PPS switching to `tx` from reader to PICC and `rx` from
+    /// PICC to reader. 106 kbps in both directions is always supported
+    pub fn supports_bit_rates(&self, tx: BitRate, rx: BitRate) -> bool {
+        if tx == BitRate::Kbps106 && rx == BitRate::Kbps106 {
+            return true;
+        }
+        let ta = self.ta.unwrap_or(0);
+        if ta & Self::TA_SAME_RATE != 0 && tx != rx {
+            return false;
+        }
+        // DR bits b1..b3 for 212, 424 and 848 kbps, DS bits b5..b7
+        let supported = |rate: BitRate, shift: u8| match rate {
+            BitRate::Kbps106 => true,
+            rate => ta & (1 << (rate as u8 - 1 + shift)) != 0,
+        };
+        supported(tx, 0) && supported(rx, 4)
+    }
+
+    /// Fastest bit rates from reader to PICC and from PICC to reader `AS3910::pps` can switch to.
+    /// The directions are independent unless the PICC requires the same rate in both
+    pub fn fastest_bit_rates(&self) -> (BitRate, BitRate) {
+        const RATES: [BitRate; 3] = [BitRate::Kbps848, BitRate::Kbps424, BitRate::Kbps212];
+        let fastest = |supported: &dyn Fn(BitRate) -> bool| {
+            RATES.into_iter().find(|&rate| supported(rate)).unwrap_or_default()
+        };
+        if self.ta.unwrap_or(0) & Self::TA_SAME_RATE != 0 {
+            let rate = fastest(&|rate| self.supports_bit_rates(rate, rate));
+            return (rate, rate);
+        }
+        (
+            fastest(&|rate| self.supports_bit_rates(rate, BitRate::Kbps106)),
+            fastest(&|rate| self.supports_bit_rates(BitRate::Kbps106, rate)),
+        )
+    }
+
+    pub fn historical_bytes(&self) -> &[u8] {
+        self.historical.get(..self.historical_len as usize).unwrap_or_default()
+    }
+}
+
+/// Retransmissions of a block after a timeout or transmission error before the exchange fails
+pub const MAX_RETRANSMISSIONS: u8 = 2;
+
+/// Longest frame exchanged in the block protocol, without CRC
+pub const MAX_FRAME_LEN: usize = 256 - 2;
+
+const PCB_I_BLOCK: u8 = 0x02;
+const PCB_R_BLOCK: u8 = 0xA2;
+const PCB_BLOCK_TYPE: u8 = 0xC0;
+const PCB_BLOCK_NUMBER: u8 = 1 << 0;
+const PCB_CHAINING: u8 = 1 << 4;
+const PCB_CID_FOLLOWING: u8 = 1 << 3;
+const PCB_NAD_FOLLOWING: u8 = 1 << 2;
+/// CID bits of the CID byte, the upper bits carry power level indication
+const CID_MASK: u8 = 0x0F;
+const PCB_R_NAK: u8 = 1 << 4;
+const PCB_S_TYPE: u8 = 0x30;
+const PCB_S_DESELECT: u8 = 0xC2;
+const PCB_S_WTX: u8 = 0xF2;
+/// WTXM bits of the S(WTX) INF field, the upper bits carry power level indication
+const WTXM_MASK: u8 = 0x3F;
+
+/// Highest frame waiting time multiplier a PICC may request with S(WTX)
+pub const MAX_WTXM: u8 = 59;
+
+/// Block received from the PICC, decoded from its PCB
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Block {
+    /// Information block, `chaining` when more of the response follows
+    I { block_number: bool, chaining: bool },
+    /// Acknowledge of a chained I-block
+    RAck { block_number: bool },
+    /// Negative acknowledge, never sent by a PICC
+    RNak { block_number: bool },
+    /// Supervisory DESELECT request or response
+    SDeselect,
+    /// Supervisory waiting time extension request
+    SWtx,
+}
+
+impl Block {
+    /// Decodes the PCB of a received block, `None` for RFU codings
+    pub fn from_pcb(pcb: u8) -> Option<Self> {
+        let block_number = pcb & PCB_BLOCK_NUMBER != 0;
+        match pcb & PCB_BLOCK_TYPE {
+            0x00 => Some(Block::I {
+                block_number,
+                chaining: pcb & PCB_CHAINING != 0,
+            }),
+            0x80 if pcb & PCB_R_NAK != 0 => Some(Block::RNak { block_number }),
+            0x80 => Some(Block::RAck { block_number }),
+            0xC0 => match pcb & PCB_S_TYPE {
+                0x00 => Some(Block::SDeselect),
+                0x30 => Some(Block::SWtx),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// State of the block protocol with a PICC activated by `AS3910::rats`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Session {
+    /// Largest frame the PICC accepts, including CRC
+    pub fsc: usize,
+    /// Largest frame the reader accepts, including CRC
+    pub fsd: usize,
+    /// Frame waiting time integer from the ATS
+    pub fwi: u8,
+    /// Start-up frame guard time integer from the ATS
+    pub sfgi: u8,
+    /// Card identifier sent in every block, `None` when the PICC doesn't support CID
+    pub cid: Option<u8>,
+    /// Node address sent in the first I-block of every exchange, see `AS3910::set_nad`
+    pub nad: Option<u8>,
+    /// Whether the PICC accepts a NAD
+    pub nad_supported: bool,
+    /// Current block number of the reader, toggled for every acknowledged block
+    pub block_number: bool,
+}
+
+impl Session {
+    pub fn new(ats: &Ats, fsdi: u8, cid: u8) -> Self {
+        Session {
+            fsc: ats.fsc(),
+            fsd: frame_size(fsdi),
+            fwi: ats.fwi,
+            sfgi: ats.sfgi,
+            cid: ats.supports_cid().then_some(cid),
+            nad: None,
+            nad_supported: ats.supports_nad(),
+            block_number: false,
+        }
+    }
+
+    /// Largest INF field of a block sent to the PICC
+    pub fn max_inf_len(&self) -> usize {
+        let prologue = 1 + self.cid.is_some() as usize + self.nad.is_some() as usize;
+        self.fsc.min(MAX_FRAME_LEN + 2).saturating_sub(prologue + 2).max(1)
+    }
+
+    /// Writes the PCB and the CID and `nad` following it to `frame`, returns the prologue length
+    fn prologue(&self, pcb: u8, nad: Option<u8>, frame: &mut [u8]) -> Option<usize> {
+        let mut len = 0;
+        let mut push = |byte: u8| -> Option<()> {
+            *frame.get_mut(len)? = byte;
+            len += 1;
+            Some(())
+        };
+        let cid_following = if self.cid.is_some() { PCB_CID_FOLLOWING } else { 0 };
+        let nad_following = if nad.is_some() { PCB_NAD_FOLLOWING } else { 0 };
+        push(pcb | cid_following | nad_following)?;
+        if let Some(cid) = self.cid {
+            push(cid)?;
+        }
+        if let Some(nad) = nad {
+            push(nad)?;
+        }
+        Some(len)
+    }
+
+    /// Writes an I-block carrying `inf` to `frame` and returns its length.
+    /// The NAD is only sent in the `first` block of a chain.
+    pub fn i_block(&self, inf: &[u8], chaining: bool, first: bool, frame: &mut [u8]) -> Option<usize> {
+        let pcb = PCB_I_BLOCK | self.block_number as u8 | if chaining { PCB_CHAINING } else { 0 };
+        let len = self.prologue(pcb, self.nad.filter(|_| first), frame)?;
+        frame.get_mut(len..len + inf.len())?.copy_from_slice(inf);
+        Some(len + inf.len())
+    }
+
+    /// Writes an R(ACK) or R(NAK) block with the current block number to `frame`
+    pub fn r_block(&self, ack: bool, frame: &mut [u8]) -> Option<usize> {
+        let pcb = PCB_R_BLOCK | self.block_number as u8 | if ack { 0 } else { PCB_R_NAK };
+        self.prologue(pcb, None, frame)
+    }
+
+    /// Writes an S(DESELECT) request to `frame`
+    pub fn deselect(&self, frame: &mut [u8]) -> Option<usize> {
+        self.prologue(PCB_S_DESELECT, None, frame)
+    }
+
+    /// Writes the S(WTX) response acknowledging the multiplier of the request `inf` to `frame`,
+    /// returns its length and the multiplier. `None` for a multiplier outside 1..=59
+    pub fn wtx_response(&self, inf: &[u8], frame: &mut [u8]) -> Option<(usize, u8)> {
+        let wtxm = inf.first()? & WTXM_MASK;
+        if wtxm == 0 || wtxm > MAX_WTXM {
+            return None;
+        }
+        let len = self.prologue(PCB_S_WTX, None, frame)?;
+        *frame.get_mut(len)? = wtxm;
+        Some((len + 1, wtxm))
+    }
+
+    /// Decodes a block received from the PICC and returns it with its INF field.
+    /// `None` for RFU codings and blocks addressed to another CID
+    pub fn parse<'a>(&self, frame: &'a [u8]) -> Option<(Block, &'a [u8])> {
+        let (&pcb, mut inf) = frame.split_first()?;
+        let block = Block::from_pcb(pcb)?;
+        if pcb & PCB_CID_FOLLOWING != 0 {
+            let (&cid, rest) = inf.split_first()?;
+            if Some(cid & CID_MASK) != self.cid {
+                return None;
+            }
+            inf = rest;
+        }
+        if matches!(block, Block::I { .. }) && pcb & PCB_NAD_FOLLOWING != 0 {
+            inf = inf.get(1..)?;
+        }
+        Some((block, inf))
+    }
+}
@@ -1,4 +1,14 @@
 #![cfg_attr(not(feature = "std"), no_std)]
+// A panic in firmware is a hard fault, so indexing, unwraps and explicit panics are denied and
+// failures are reported as errors. Arithmetic is not linted: lengths are bounded by the FIFO and
+// frame sizes, offsets taken from received frames use checked operations.
+#![deny(
+    clippy::indexing_slicing,
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::unreachable
+)]
 
 extern crate delog;
 #[macro_use]
@@ -12,15 +22,77 @@ use hal::digital::v2::OutputPin;
 use hal::prelude::_embedded_hal_blocking_spi_Transfer;
 use hal::prelude::_embedded_hal_blocking_spi_Write;
 
+use apdu::{Apdu, ApduResponse, Fci};
+use calibration::{
+    AntennaCalibrationResult, AntennaResonance, CalibrationProfile, MaintenanceReport, ModulationDepthResult,
+};
 use command::Command;
-use register::{Register, InterruptFlags};
+use iso_dep::{Ats, Block, Session};
+use measurement::{Millivolts, ReceiverState, RegulatorStatus};
+use nfc::{ExternalFieldEvent, ResponseCollisionAvoidance, RfcaOutcome};
+use self_test::SelfTestReport;
+use stats::Stats;
+use timing::frame_duration_us;
+use config::{
+    AdaptivePower, AmModulatedLevel, AntennaTrim, BitRate, Config, Demodulation, FieldDetectionThreshold,
+    FifoWaterLevel, InterruptLine, Iso14443aConfig, ModulationDepthConfig, NonModulatedLevel, OperatingMode,
+    OperationControlConfig, ReceiverConfig, Recovery, RegisterConfig, RegulatedVoltage, RegulatorVoltage,
+    RetryPolicy, Timeouts,
+};
+use register::{
+    CollisionFlags, Configuration5Flags, FifoStatusFlags, InterruptFlags,
+    NumberOfTransmittedBytes0Flags, OperationControlFlags, Register, RegisterDump, REGISTER_COUNT,
+};
 
 mod picc;
+pub use picc::{CardType, Sak};
+pub mod apdu;
+pub mod calibration;
 pub mod command;
+pub mod config;
+pub mod crc;
+pub mod irq;
+pub mod iso_dep;
+pub mod measurement;
+pub mod nfc;
 pub mod register;
+pub mod self_test;
+pub mod stats;
+pub mod timing;
 
 delog::generate_macros!();
 
+// Uses the log macros, which are only in scope after their definition
+#[cfg(feature = "magic")]
+pub mod magic;
+
+/// Largest number of complete bytes in a transmitted frame, limited by the 10 bit `ntx` count
+const MAX_FRAME_LEN: usize = 1023;
+
+/// Size of the FIFO shared by transmit and receive
+const FIFO_SIZE: usize = 32;
+
+/// Time the field stays off when cycling it, enough for PICCs to reset (ISO/IEC 14443-3)
+const FIELD_RESET_MS: u16 = 5;
+
+/// Registers which read 0 after Set Default, checked by `AS3910::probe`
+const ZERO_AFTER_RESET: [Register; 8] = [
+    Register::ModeDefinition,
+    Register::OperationControl,
+    Register::ConfigurationRegister2,
+    Register::ConfigurationRegister3,
+    Register::ConfigurationRegister4,
+    Register::ConfigurationRegister5,
+    Register::MaskInterrupt,
+    Register::ExternalTrim,
+];
+
+/// Consecutive asserted polls with an empty Interrupt Register before the line is reported as stuck
+const IRQ_STUCK_READS: u8 = 3;
+
+/// Upper bound for the crystal oscillator to become stable after enabling it (typical 0.7ms)
+const OSCILLATOR_TIMEOUT_MS: u16 = 10;
+
 #[derive(Debug)]
 pub enum SPIOrCSError<E, OPE> {
     SPI(E),
@@ -41,11 +113,100 @@ pub trait SpiWithCustomCS {
         CS: OutputPin<Error = OPE>;
 }
 
+/// Bus-level transaction reported to the trace hook
+#[derive(Debug)]
+pub enum SpiEvent<'a> {
+    ReadRegister { reg: Register, value: u8 },
+    WriteRegister { reg: Register, value: u8 },
+    ReadRegisters { start: Register, values: &'a [u8] },
+    WriteRegisters { start: Register, values: &'a [u8] },
+    ReadFifo(&'a [u8]),
+    WriteFifo(&'a [u8]),
+    Command(Command),
+}
+
 /// Answer To reQuest A
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct AtqA {
     pub bytes: [u8; 2],
 }
 
+/// UID size announced in the ATQA
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UidSize {
+    /// 4 bytes, one cascade level
+    Single,
+    /// 7 bytes, two cascade levels
+    Double,
+    /// 10 bytes, three cascade levels
+    Triple,
+}
+
+impl UidSize {
+    /// Number of UID bytes
+    pub fn bytes(&self) -> usize {
+        match self {
+            UidSize::Single => 4,
+            UidSize::Double => 7,
+            UidSize::Triple => 10,
+        }
+    }
+
+    /// Number of cascade levels needed to select the PICC
+    pub fn cascade_levels(&self) -> u8 {
+        match self {
+            UidSize::Single => 1,
+            UidSize::Double => 2,
+            UidSize::Triple => 3,
+        }
+    }
+}
+
+impl AtqA {
+    /// UID size bit frame, `None` for the RFU coding
+    pub fn uid_size(&self) -> Option<UidSize> {
+        match (self.bytes[0] >> 6) & 0b11 {
+            0b00 => Some(UidSize::Single),
+            0b01 => Some(UidSize::Double),
+            0b10 => Some(UidSize::Triple),
+            _ => None,
+        }
+    }
+
+    /// Bit frame anticollision bits (b1..b5 of the first byte)
+    pub fn bit_frame_anticollision(&self) -> u8 {
+        self.bytes[0] & 0b1_1111
+    }
+
+    /// Whether the PICC supports bit frame anticollision, exactly one bit must be set
+    pub fn supports_bit_frame_anticollision(&self) -> bool {
+        self.bit_frame_anticollision().count_ones() == 1
+    }
+
+    /// Proprietary coding (b1..b4 of the second byte)
+    pub fn proprietary_coding(&self) -> u8 {
+        self.bytes[1] & 0b1111
+    }
+}
+
+/// Everything learned about a PICC while activating it with `AS3910::poll`
+#[derive(Hash, Eq, PartialEq)]
+pub struct Card {
+    pub uid: Uid,
+    pub atqa: AtqA,
+    /// SAK of the last cascade level
+    pub sak: Sak,
+}
+
+impl Card {
+    /// PICC type according to its ATQA and SAK
+    pub fn card_type(&self) -> CardType {
+        CardType::from(self.atqa, self.sak)
+    }
+}
+
 #[derive(Hash, Eq, PartialEq)]
 pub enum Uid {
     /// Single sized UID, 4 bytes long
@@ -64,6 +225,55 @@ impl Uid {
             Uid::Triple(u) => u.as_bytes(),
         }
     }
+
+    /// SAK of the last cascade level
+    pub fn sak(&self) -> Sak {
+        match &self {
+            Uid::Single(u) => u.sak,
+            Uid::Double(u) => u.sak,
+            Uid::Triple(u) => u.sak,
+        }
+    }
+
+    /// Kind of UID according to its first byte uid0, see `Uid0Class`
+    pub fn uid0_class(&self) -> Uid0Class {
+        match self {
+            Uid::Double(GenericUid { bytes: [uid0, ..], .. })
+            | Uid::Triple(GenericUid { bytes: [uid0, ..], .. }) => Uid0Class::Unique { manufacturer: *uid0 },
+            Uid::Single(GenericUid { bytes: [uid0, ..], .. }) => match *uid0 {
+                0x08 => Uid0Class::Random,
+                uid0 if uid0 & 0x0F == 0x0F => Uid0Class::NonUnique,
+                uid0 if uid0 & 0x0F == 0x08 => Uid0Class::Rfu,
+                _ => Uid0Class::Fixed,
+            },
+        }
+    }
+
+    /// Whether the PICC generates a new random UID for every activation. Such a UID only
+    /// identifies the PICC until it leaves the field and must not be used as an identity
+    pub fn is_random(&self) -> bool {
+        self.uid0_class() == Uid0Class::Random
+    }
+}
+
+/// Classification of a UID by its first byte, following ISO/IEC 14443-3 and NXP AN10927.
+///
+/// Only double and triple size UIDs are guaranteed to be unique. The 4 byte UID space is
+/// exhausted, fixed 4 byte UIDs are reused by several PICCs and random ones change with every
+/// activation, neither is a safe credential for access control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Uid0Class {
+    /// 7 or 10 byte UID, unique per IC manufacturer, whose ISO/IEC 7816-6 code is uid0
+    Unique { manufacturer: u8 },
+    /// 4 byte random ID (RID, uid0 0x08), dynamically generated
+    Random,
+    /// 4 byte fixed non-unique ID (NUID, uid0 0xxF)
+    NonUnique,
+    /// Other 4 byte fixed ID, proprietary and not guaranteed to be unique (ONUID)
+    Fixed,
+    /// 4 byte UID with an RFU uid0 (0xx8 besides 0x08)
+    Rfu,
 }
 
 #[derive(Hash, Eq, PartialEq)]
@@ -93,30 +303,252 @@ pub struct FifoData<const L: usize> {
     buffer: [u8; L],
     /// The number of valid bytes in the buffer
     valid_bytes: usize,
+    /// The number of valid bits in the last byte, 8 unless reception stopped at a bit collision.
+    /// The chip doesn't report incomplete last bytes otherwise
+    last_byte_bits: u8,
 }
 
 impl<const L: usize> FifoData<L> {
+    /// The number of valid bits in the buffer
+    pub fn valid_bits(&self) -> usize {
+        if self.valid_bytes == 0 {
+            return 0;
+        }
+        (self.valid_bytes - 1) * 8 + self.last_byte_bits as usize
+    }
+
+    /// The number of valid bits in the last byte
+    pub fn last_byte_bits(&self) -> u8 {
+        self.last_byte_bits
+    }
+
+    /// The received bytes, the last one possibly incomplete
+    pub fn as_bytes(&self) -> &[u8] {
+        self.buffer.get(..self.valid_bytes).unwrap_or(&self.buffer)
+    }
+
     /// Copies FIFO data to destination buffer.
     /// Assumes the FIFO data is aligned properly to append directly to the current known bits.
     /// Returns the number of valid bits in the destination buffer after copy.
-    pub fn copy_bits_to(&self, dst: &mut [u8], dst_valid_bits: u8) -> u8 {
+    pub fn copy_bits_to(&self, dst: &mut [u8], dst_valid_bits: u8) -> Result<u8, CopyError> {
         if self.valid_bytes == 0 {
-            return dst_valid_bits;
+            return Ok(dst_valid_bits);
         }
         let dst_valid_bytes = dst_valid_bits / 8;
         let dst_valid_last_bits = dst_valid_bits % 8;
         let mask: u8 = 0xFF << dst_valid_last_bits;
-        let mut idx = dst_valid_bytes as usize;
-        dst[idx] = (self.buffer[0] & mask) | (dst[idx] & !mask);
-        idx += 1;
+        let idx = dst_valid_bytes as usize;
         let len = self.valid_bytes - 1;
-        if len > 0 {
-            dst[idx..idx + len].copy_from_slice(&self.buffer[1..=len]);
+        if idx + 1 + len > dst.len() {
+            return Err(CopyError::DestinationTooSmall);
+        }
+        let valid_bits = dst_valid_bits as usize + len * 8;
+        if valid_bits > u8::MAX as usize {
+            return Err(CopyError::TooManyBits);
+        }
+
+        let src = self.buffer.get(..=len).ok_or(CopyError::DestinationTooSmall)?;
+        let dst = dst.get_mut(idx..=idx + len).ok_or(CopyError::DestinationTooSmall)?;
+        if let (Some((dst_first, dst_rest)), Some((src_first, src_rest))) = (dst.split_first_mut(), src.split_first()) {
+            *dst_first = (src_first & mask) | (*dst_first & !mask);
+            dst_rest.copy_from_slice(src_rest);
+        }
+        Ok(valid_bits as u8)
+    }
+}
+
+/// Reason `FifoData::copy_bits_to` refused to copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyError {
+    /// Destination buffer can't hold the FIFO data at the given bit offset
+    DestinationTooSmall,
+    /// Resulting number of valid bits doesn't fit in `u8`
+    TooManyBits,
+}
+
+/// Framing and timing of a single `AS3910::transceive`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransceiveOptions {
+    /// Append CRC on transmit and check (and remove) it on receive
+    pub crc: bool,
+    /// ISO-14443A bit oriented anticollision frame, the response is aligned to `tx_last_bits`.
+    /// The AS3910 has no other receive alignment, a response always starts at bit 0 otherwise
+    pub anticollision: bool,
+    /// Number of bits of the last transmitted byte, 0 when it is complete
+    pub tx_last_bits: u8,
+    /// Time to wait for the response after the end of transmission
+    pub timeout_ms: u16,
+    /// Check the parity of received bytes, `Iso14443aConfig::no_parity_check` disables it for
+    /// every frame. The AS3910 always generates parity on transmit and strips it on receive,
+    /// frames with caller supplied parity bits (MIFARE Classic encrypted exchanges) need
+    /// transparent mode
+    pub parity_check: bool,
+    /// The response is a 4 bit frame without parity, like the MIFARE ACK/NAK. The chip puts its
+    /// bits in the high nibble of the only received byte (datasheet §8.14). The frame has no
+    /// CRC either, combine it with `crc(false)`
+    pub four_bit_response: bool,
+}
+
+impl Default for TransceiveOptions {
+    fn default() -> Self {
+        Self {
+            crc: true,
+            anticollision: false,
+            tx_last_bits: 0,
+            timeout_ms: timing::iso_dep_timeout_ms(timing::DEFAULT_FWI),
+            parity_check: true,
+            four_bit_response: false,
+        }
+    }
+}
+
+impl TransceiveOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn crc(mut self, crc: bool) -> Self {
+        self.crc = crc;
+        self
+    }
+
+    pub fn anticollision(mut self, anticollision: bool) -> Self {
+        self.anticollision = anticollision;
+        self
+    }
+
+    pub fn tx_last_bits(mut self, bits: u8) -> Self {
+        self.tx_last_bits = bits;
+        self
+    }
+
+    pub fn timeout_ms(mut self, timeout_ms: u16) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    pub fn parity_check(mut self, parity_check: bool) -> Self {
+        self.parity_check = parity_check;
+        self
+    }
+
+    pub fn four_bit_response(mut self, four_bit_response: bool) -> Self {
+        self.four_bit_response = four_bit_response;
+        self
+    }
+
+    /// Sets the timeout to the ISO-DEP frame waiting time of `fwi` plus ΔFWT
+    pub fn frame_waiting_time(mut self, fwi: u8) -> Self {
+        self.timeout_ms = timing::iso_dep_timeout_ms(fwi);
+        self
+    }
+}
+
+/// Condition checked by `AS3910::wait_for_flags`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WaitMode {
+    /// Done as soon as one of the flags fired
+    Any,
+    /// Done once every flag fired, possibly spread over several interrupts
+    All,
+}
+
+impl WaitMode {
+    fn satisfied(self, fired: InterruptFlags, flags: InterruptFlags) -> bool {
+        match self {
+            WaitMode::Any => fired.intersects(flags),
+            WaitMode::All => fired.contains(flags),
+        }
+    }
+}
+
+/// Size of a response received with `AS3910::transceive`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Received {
+    /// The number of bytes written to the receive buffer
+    pub bytes: usize,
+    /// The number of valid bits in the last byte, see `FifoData::last_byte_bits`.
+    /// 4 for a `TransceiveOptions::four_bit_response` frame
+    pub last_byte_bits: u8,
+    /// Every interrupt flag raised during the exchange, see `InterruptFlags::events`
+    pub interrupts: InterruptFlags,
+}
+
+impl Received {
+    /// The number of valid bits received
+    pub fn bits(&self) -> usize {
+        if self.bytes == 0 {
+            return 0;
         }
-        dst_valid_bits + (len * 8) as u8
+        (self.bytes - 1) * 8 + self.last_byte_bits as usize
     }
 }
 
+/// Position of a bit collision, decoded from the Collision Register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CollisionPosition {
+    /// Number of complete bytes of the frame (transmitted and received) before the collision
+    pub bytes: u8,
+    /// Number of bits before the collision in the byte where it happened
+    pub bits: u8,
+    /// Whether the collision revealed new UID bits, `false` when anticollision had to give up
+    pub progress: bool,
+}
+
+impl CollisionPosition {
+    fn from_register(bits: u8) -> Self {
+        let flags = CollisionFlags::from_bits_truncate(bits);
+        Self {
+            bytes: (flags & CollisionFlags::C_BYTE).bits() >> 4,
+            bits: (flags & CollisionFlags::C_BIT).bits() >> 1,
+            progress: true,
+        }
+    }
+
+    /// Index of the collided bit within the UID CLn and BCC of an anticollision frame (0 to 39),
+    /// counted from the LSB of the first UID byte. `None` when the position is outside of the
+    /// UID or not after the `known_bits` already sent, so anticollision can't make progress.
+    pub fn uid_bit(&self, known_bits: u8) -> Option<u8> {
+        // Position counts the SEL and NVB bytes as well
+        let bit = self.bytes.checked_sub(2)?.checked_mul(8)?.checked_add(self.bits)?;
+        (known_bits..40).contains(&bit).then_some(bit)
+    }
+
+    /// Same position, marked as not allowing any progress
+    pub fn without_progress(self) -> Self {
+        Self { progress: false, ..self }
+    }
+}
+
+/// Sets `bit` (counted as in `CollisionPosition::uid_bit`) in the anticollision frame `tx`,
+/// which starts with the SEL and NVB bytes. `None` when the bit is outside of `tx`.
+fn set_uid_bit(tx: &mut [u8], bit: u8) -> Option<()> {
+    let byte = tx.get_mut(2 + (bit / 8) as usize)?;
+    *byte |= 1 << (bit % 8);
+    Some(())
+}
+
+/// Registers captured when a protocol error is generated. Only filled with the `debug-snapshots`
+/// feature, otherwise it is empty and costs nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DebugSnapshot {
+    /// FIFO Status Register
+    #[cfg(feature = "debug-snapshots")]
+    pub fifo_status: u8,
+    /// Interrupts of the exchange and still pending ones, the register itself clears on read
+    #[cfg(feature = "debug-snapshots")]
+    pub interrupts: InterruptFlags,
+    /// Collision Register
+    #[cfg(feature = "debug-snapshots")]
+    pub collision: u8,
+    /// Receiver State Display Register
+    #[cfg(feature = "debug-snapshots")]
+    pub receiver_state: u8,
+}
+
 pub struct AS3910<SPICS, CS, INTR, DELAY> {
     spi_with_custom_cs: SPICS,
     // Chip select pin
@@ -124,6 +556,28 @@ pub struct AS3910<SPICS, CS, INTR, DELAY> {
     /// Interrupt pin
     intr: INTR,
     delay: DELAY,
+    /// Optional observer invoked for every SPI transaction
+    trace_hook: Option<fn(&SpiEvent)>,
+    /// Interrupts delivered by the ISR, replaces polling the interrupt line when set
+    event_source: Option<fn() -> Option<irq::IrqEvent>>,
+    /// Last written value of every register, `None` when not written since reset
+    shadow: [Option<u8>; REGISTER_COUNT],
+    /// Receiver configuration written before every transceive
+    receiver_config: ReceiverConfig,
+    /// ISO-14443A framing configuration, CRC handling is overridden per transceive
+    iso14443a_config: Iso14443aConfig,
+    /// Currently selected protocol mode
+    mode: OperatingMode,
+    config: Config,
+    /// Every interrupt flag read from the chip since the last `take_interrupts`
+    latched: InterruptFlags,
+    /// Consecutive polls which found the interrupt line asserted but no flag set
+    empty_interrupt_reads: u8,
+    /// Interrupt sources kept enabled across operations, by subscriptions and field detection
+    persistent_interrupts: InterruptFlags,
+    stats: Stats,
+    /// Block protocol state of the PICC activated with `rats`
+    iso_dep: Option<Session>,
 }
 
 impl<OPE, CS, INTR, SPICS, DELAY> AS3910<SPICS, CS, INTR, DELAY>
@@ -131,88 +585,204 @@ where
     SPICS: SpiWithCustomCS,
     CS: OutputPin<Error = OPE>,
     INTR: InputPin<Error = OPE>,
-    DELAY: delay::DelayMs<u16>,
+    DELAY: delay::DelayMs<u16> + delay::DelayUs<u16>,
 {
 
     pub fn new(spi_with_custom_cs: SPICS, cs: CS, intr: INTR, delay: DELAY) -> Result<Self, Error<SPICS::SpiError, OPE>> {
+        Self::with_config(spi_with_custom_cs, cs, intr, delay, Config::default())
+    }
+
+    pub fn with_config(
+        spi_with_custom_cs: SPICS,
+        cs: CS,
+        intr: INTR,
+        delay: DELAY,
+        config: Config,
+    ) -> Result<Self, Error<SPICS::SpiError, OPE>> {
         let mut as3910 = Self {
             spi_with_custom_cs,
             cs,
             intr,
             delay,
+            trace_hook: None,
+            event_source: None,
+            shadow: [None; REGISTER_COUNT],
+            receiver_config: ReceiverConfig::default(),
+            iso14443a_config: Iso14443aConfig::default(),
+            mode: OperatingMode::default(),
+            config,
+            latched: InterruptFlags::empty(),
+            empty_interrupt_reads: 0,
+            persistent_interrupts: InterruptFlags::empty(),
+            stats: Stats::default(),
+            iso_dep: None,
         };
-        as3910.reset()?;
-        // TODO: investigate and write comment
-        as3910.write_register(Register::RegulatedVoltageDefinition, 0xA8)?;
+        as3910.probe()?;
+        as3910.set_regulated_voltage(config.regulated_voltage)?;
+        as3910.start_oscillator()?;
+
+        as3910.set_antenna_trim(config.antenna_trim)?;
+        if config.antenna_trim == AntennaTrim::Calibrated {
+            let calibration = as3910.calibrate_antenna()?;
+            if calibration.error {
+                return Err(Error::AntennaCalibration(calibration));
+            }
+        }
+
+        // PM demodulation
+        // as3910.set_demodulation(Demodulation::Pm)?;
+        as3910.wake_up()?;
+
+        Ok(as3910)
+    }
+
+    /// Installs (or removes with `None`) a hook that is called for every register access,
+    /// FIFO access and direct command. Useful to capture bus-level transcripts.
+    pub fn set_trace_hook(&mut self, hook: Option<fn(&SpiEvent)>) {
+        self.trace_hook = hook;
+    }
 
-        as3910.execute_command(Command::CalibrateAntenna)?;
+    /// Installs (or removes with `None`) the function returning the next interrupt event pushed
+    /// by the ISR, see `irq`. While it is set every interrupt wait consumes its events instead
+    /// of checking `Config::interrupt_line`.
+    pub fn set_event_source(&mut self, source: Option<fn() -> Option<irq::IrqEvent>>) {
+        self.event_source = source;
+    }
 
-        as3910.delay.delay_ms(1);
-        let val = as3910.read_register(Register::AntennaCalibration)?;
+    pub fn reset(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.execute_command(Command::SetDefault)
+    }
 
-        if val & 0x8 != 0 {
-            return Err(Error::AntennaCalibration);
+    /// Resets the chip and checks that it responds like an AS3910: registers read their reset value
+    /// and a written pattern reads back. Fails with `Error::ChipNotFound` otherwise, which points to
+    /// wiring, chip select or SPI mode problems.
+    pub fn probe(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.reset()?;
+        for reg in ZERO_AFTER_RESET {
+            let value = self.read_register(reg)?;
+            if value != 0 {
+                error!("Probe: {:?} reads 0x{:02x} after reset", reg, value);
+                return Err(Error::ChipNotFound);
+            }
         }
-        // Enables oscillator and regulator
+        // A MISO line stuck low reads the reset values as well
+        if !self.check_scratch_register()? {
+            error!("Probe: written pattern doesn't read back");
+            return Err(Error::ChipNotFound);
+        }
+        Ok(())
+    }
+
+    /// Enters Ready mode: oscillator and regulators stay enabled, receiver and RF output are off.
+    /// Allows a quick `wake_up` without waiting for the oscillator.
+    pub fn standby(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.apply(OperationControlConfig::new().oscillator_and_regulators(true))
+    }
+
+    /// Enters Power-down mode: oscillator, regulators, receiver and RF output are off.
+    /// Register content is retained, the EN pin has to be low as well.
+    pub fn power_down(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.apply(OperationControlConfig::new())
+    }
+
+    /// Leaves Power-down or Ready mode: starts the oscillator, enables receiver and RF output
+    /// and re-arms the receive interrupt
+    pub fn wake_up(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.start_oscillator()?;
+
         // Enables receiver operation
         // Enables RF output
-        as3910.write_register(Register::OperationControl, 0xD0)?;
+        self.apply(
+            OperationControlConfig::new()
+                .oscillator_and_regulators(true)
+                .receiver(true)
+                .transmitter(true),
+        )?;
+        self.delay.delay_ms(self.config.field_guard_ms);
 
-        // PM demodulation
-        // as3910.write_register(Register::ConfigurationRegister5, 0b1000_0000)?;
-        as3910.execute_command(Command::Clear)?;
+        self.execute_command(Command::Clear)?;
+        self.setup_interrupt_mask(InterruptFlags::END_OF_RECEIVE)?;
+        Ok(())
+    }
 
-        as3910.setup_interrupt_mask(InterruptFlags::END_OF_RECEIVE)?;
+    /// Enables oscillator and regulators and waits for the oscillator frequency to be stable.
+    /// Returns immediately when they are already running.
+    fn start_oscillator(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        let control = OperationControlFlags::from_bits_truncate(self.read_register(Register::OperationControl)?);
+        if control.contains(OperationControlFlags::EN) {
+            return Ok(());
+        }
 
-        Ok(as3910)
+        self.setup_interrupt_mask(InterruptFlags::OSCILLATOR_FREQUENCY_STABLE)?;
+        self.apply(OperationControlConfig::new().oscillator_and_regulators(true))?;
+        let flags = self.wait_for_interrupt(OSCILLATOR_TIMEOUT_MS)?;
+        if !flags.contains(InterruptFlags::OSCILLATOR_FREQUENCY_STABLE) {
+            return Err(Error::InterruptTimeout);
+        }
+        Ok(())
     }
 
-    pub fn reset(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
-        self.execute_command(Command::SetDefault)
+    /// Sends a REQuest type A to nearby PICCs, fails with `Error::NoCard` when none answers
+    pub fn reqa(&mut self) -> Result<AtqA, Error<SPICS::SpiError, OPE>> {
+        let timeout_ms = self.config.timeouts.request_ms;
+        self.with_retry(|as3910| as3910.reqa_with_timeout(timeout_ms))
     }
 
-    /// Sends a REQuest type A to nearby PICCs
-    pub fn reqa(&mut self) -> Result<Option<AtqA>, Error<SPICS::SpiError, OPE>> {
+    /// Sends a REQuest type A, waiting up to `timeout_ms` for the ATQA
+    pub fn reqa_with_timeout(&mut self, timeout_ms: u16) -> Result<AtqA, Error<SPICS::SpiError, OPE>> {
         info!("reqa");
         self.execute_command(Command::Clear)?;
-        self.write_register(Register::ConfigurationRegister3, 0x80)?;
+        self.apply(self.iso14443a_config.receive_without_crc(true))?;
         self.setup_interrupt_mask(InterruptFlags::END_OF_RECEIVE)?;
         self.execute_command(Command::TransmitREQA)?;
 
-        self.wait_for_interrupt(5)?;
+        match self.wait_for_interrupt(timeout_ms) {
+            // No PICC in area
+            Err(Error::InterruptTimeout) => return Err(Error::NoCard),
+            result => result?,
+        };
 
         let fifo_reg = self.read_register(Register::FIFOStatus)?;
 
         if fifo_reg >> 2 == 0b00111111 {
             // No PICC in area
-            return Ok(None);
+            return Err(Error::NoCard);
         }
         let mut buffer = [0u8; 2];
 
         self.read_fifo(&mut buffer)?;
 
-        Ok(Some(AtqA { bytes: buffer }))
+        Ok(AtqA { bytes: buffer })
+    }
+
+    /// Sends a Wake UP type A to nearby PICCs, fails with `Error::NoCard` when none answers
+    pub fn wupa(&mut self) -> Result<AtqA, Error<SPICS::SpiError, OPE>> {
+        self.wupa_with_timeout(self.config.timeouts.request_ms)
     }
 
-    /// Sends a Wake UP type A to nearby PICCs
-    pub fn wupa(&mut self) -> Result<Option<AtqA>, Error<SPICS::SpiError, OPE>> {
+    /// Sends a Wake UP type A, waiting up to `timeout_ms` for the ATQA
+    pub fn wupa_with_timeout(&mut self, timeout_ms: u16) -> Result<AtqA, Error<SPICS::SpiError, OPE>> {
         info!("wupa");
         self.setup_interrupt_mask(InterruptFlags::END_OF_RECEIVE)?;
         self.execute_command(Command::TransmitWUPA)?;
 
-        self.wait_for_interrupt(5)?;
+        match self.wait_for_interrupt(timeout_ms) {
+            // No PICC in area
+            Err(Error::InterruptTimeout) => return Err(Error::NoCard),
+            result => result?,
+        };
 
         let fifo_reg = self.read_register(Register::FIFOStatus)?;
 
         if fifo_reg >> 2 == 0b00111111 {
             // No PICC in area
-            return Ok(None);
+            return Err(Error::NoCard);
         }
         let mut buffer = [0u8; 2];
 
         self.read_fifo(&mut buffer)?;
 
-        Ok(Some(AtqA { bytes: buffer }))
+        Ok(AtqA { bytes: buffer })
     }
 
     /// Sends command to enter HALT state
@@ -223,15 +793,71 @@ where
         //   after the end of the frame containing the HLTA command,
         //   this response shall be interpreted as 'not acknowledge'.
         // We interpret that this way: Only Error::Timeout is a success.
-        match self.communicate_to_picc::<0>(&[0x50, 0x00], 0, false, true) {
-            Err(Error::InterruptTimeout) => Ok(()),
+        let options = TransceiveOptions::new().timeout_ms(self.config.timeouts.hlta_ms);
+        match self.communicate_to_picc::<0>(&[0x50, 0x00], options) {
+            Err(Error::Timeout(_)) => Ok(()),
             Ok(_) => Err(Error::NotAcknowledged),
             Err(e) => Err(e),
         }
     }
 
+    /// Collects the UIDs of all PICCs in the field into `cards` and returns how many were found,
+    /// at most `cards.len()`. Every PICC is selected and halted in turn, so the next REQA
+    /// is only answered by the remaining ones. PICCs halted before are not found.
+    pub fn enumerate_cards(&mut self, cards: &mut [Option<Uid>]) -> Result<usize, Error<SPICS::SpiError, OPE>> {
+        let mut count = 0;
+        while count < cards.len() {
+            match self.reqa() {
+                Err(Error::NoCard) => break,
+                result => result?,
+            };
+            // Retries wake the PICC with REQA, WUPA would wake the ones halted before as well
+            let request_ms = self.config.timeouts.request_ms;
+            let uid = self.select_retrying(None, |as3910| as3910.reqa_with_timeout(request_ms))?;
+            self.hlta()?;
+            // A PICC which didn't halt answers again, stop instead of collecting it forever
+            if cards.iter().take(count).flatten().any(|card| *card == uid) {
+                warn!("PICC {:x?} answered again after HLTA", uid.as_bytes());
+                break;
+            }
+            if let Some(slot) = cards.get_mut(count) {
+                *slot = Some(uid);
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Runs the anticollision and selection of all cascade levels, retried according to `Config::retry`.
+    /// Every retry starts over with WUPA, since the PICC went back to IDLE when a cascade level failed.
     pub fn select(&mut self) -> Result<Uid, Error<SPICS::SpiError, OPE>> {
+        self.select_retrying(None, Self::wupa)
+    }
+
+    /// Like `select`, using the UID size announced in `atqa`. Every cascade level but the last
+    /// starts with the cascade tag as known UID CLn byte, so fewer bits go through anticollision.
+    /// A card whose UID is already known is selected faster with `select_uid`.
+    pub fn select_with_atqa(&mut self, atqa: &AtqA) -> Result<Uid, Error<SPICS::SpiError, OPE>> {
+        self.select_retrying(atqa.uid_size(), Self::wupa)
+    }
+
+    /// Runs `select_once` according to `Config::retry`, sending `wake_up` before every retry
+    fn select_retrying<W>(&mut self, uid_size: Option<UidSize>, mut wake_up: W) -> Result<Uid, Error<SPICS::SpiError, OPE>>
+    where
+        W: FnMut(&mut Self) -> Result<AtqA, Error<SPICS::SpiError, OPE>>,
+    {
+        let mut retry = false;
+        self.with_retry(|as3910| {
+            if core::mem::replace(&mut retry, true) {
+                wake_up(as3910)?;
+            }
+            as3910.select_once(uid_size)
+        })
+    }
+
+    fn select_once(&mut self, uid_size: Option<UidSize>) -> Result<Uid, Error<SPICS::SpiError, OPE>> {
         info!("Select");
+        let cascade_levels = uid_size.map_or(1, |size| size.cascade_levels());
         let mut cascade_level: u8 = 0;
         let mut uid_bytes: [u8; 10] = [0u8; 10];
         let mut uid_idx: usize = 0;
@@ -240,20 +866,25 @@ where
                 0 => picc::Command::SelCl1,
                 1 => picc::Command::SelCl2,
                 2 => picc::Command::SelCl3,
-                _ => unreachable!(),
+                _ => return Err(Error::InvalidResponse),
             };
             let mut known_bits = 0;
             let mut tx = [0u8; 9];
             tx[0] = cmd as u8;
-            let mut anticollision_cycle_counter = 0;
+            // The UID continues in the next cascade level, so this one starts with the cascade tag
+            if cascade_level + 1 < cascade_levels {
+                tx[2] = picc::Command::CT as u8;
+                known_bits = 8;
+            }
+            let mut anticollision_cycle_counter: u8 = 0;
 
             debug!("Select with cascade {}", cascade_level);
             'anticollision: loop {
-                anticollision_cycle_counter += 1;
+                anticollision_cycle_counter = anticollision_cycle_counter.saturating_add(1);
                 debug!("Stating anticollision loop nr {} read uid_bytes {:x?}", anticollision_cycle_counter, uid_bytes);
 
-                if anticollision_cycle_counter > 32 {
-                    return Err(Error::AntiCollisionMaxLoopsReached);
+                if anticollision_cycle_counter > self.config.anticollision_max_loops {
+                    return Err(Error::AntiCollisionMaxLoopsReached(self.config.anticollision_max_loops));
                 }
                 let tx_last_bits = known_bits % 8;
                 let tx_bytes = 2 + known_bits / 8;
@@ -263,221 +894,1747 @@ where
                 // Tell transceive the only send `tx_last_bits` of the last byte
                 // and also to put the first received bit at location `tx_last_bits`.
                 // This makes it easier to append the received bits to the uid (in `tx`).
-                match self.communicate_to_picc::<5>(&tx[0..end], tx_last_bits, true, false) {
+                let options = TransceiveOptions::new()
+                    .crc(false)
+                    .anticollision(true)
+                    .tx_last_bits(tx_last_bits)
+                    .timeout_ms(self.config.timeouts.anticollision_ms);
+                let frame = tx.get(..end).ok_or(Error::InvalidResponse)?;
+                match self.communicate_to_picc::<5>(frame, options) {
                     Ok(fifo_data) => {
-                        fifo_data.copy_bits_to(&mut tx[2..=6], known_bits);
+                        fifo_data.copy_bits_to(&mut tx[2..=6], known_bits).map_err(|_| Error::NoRoom)?;
                         debug!("Read full response {:?}", fifo_data);
                         break 'anticollision;
                     }
-                    Err(Error::Collision) => {
-                        let coll_reg = self.read_register(Register::Collision)?;
-
-                        let bytes_before_coll = ((coll_reg >> 4) & 0b1111) - 2;
-                        let bits_before_coll = (coll_reg >> 1) & 0b111;
-
-                        let coll_pos = bytes_before_coll * 8 + bits_before_coll + 1;
-
-                        if coll_pos < known_bits || coll_pos > 8 * 9 {
+                    Err(Error::Collision(position, snapshot)) => {
+                        let Some(collision_bit) = position.uid_bit(known_bits) else {
                             // No progress
-                            return Err(Error::Collision);
-                        }
+                            return Err(Error::Collision(position.without_progress(), snapshot));
+                        };
 
-                        let fifo_data = self.fifo_data::<5>()?;
+                        let mut fifo_data = self.fifo_data::<5>()?;
+                        fifo_data.last_byte_bits = position.bits;
                         debug!("Read partial response {:?}", fifo_data);
 
-                        fifo_data.copy_bits_to(&mut tx[2..=6], known_bits);
-                        known_bits = coll_pos;
+                        fifo_data.copy_bits_to(&mut tx[2..=6], known_bits).map_err(|_| Error::NoRoom)?;
 
-                        // Set the bit of collision position to 1
-                        let count = known_bits % 8;
-                        let check_bit = (known_bits - 1) % 8;
-                        let index: usize =
-                            1 + (known_bits / 8) as usize + if count != 0 { 1 } else { 0 };
-                        // TODO safe check that index is in range
-                        tx[index] |= 1 << check_bit;
+                        // Choose the PICCs with a 1 at the collision position, which becomes known as well
+                        set_uid_bit(&mut tx, collision_bit)
+                            .ok_or(Error::Collision(position.without_progress(), snapshot))?;
+                        known_bits = collision_bit + 1;
                     }
                     Err(e) => return Err(e),
                 }
             }
 
-            // send select
-            tx[1] = 0x70; // NVB: 7 valid bytes
-            tx[6] = tx[2] ^ tx[3] ^ tx[4] ^ tx[5]; // BCC
-
-            let rx = self.communicate_to_picc::<1>(&tx[0..7], 0, false, true)?;
-            // println!("rx {:?}", rx);
+            // The PICC sent the BCC along with the UID CLn, a mismatch means a corrupted UID
+            if tx[6] != tx[2] ^ tx[3] ^ tx[4] ^ tx[5] {
+                return Err(Error::Bcc);
+            }
 
-            let sak = picc::Sak::from(rx.buffer[0]);
+            let sak = self.send_select(cmd, [tx[2], tx[3], tx[4], tx[5]])?;
 
             if !sak.is_complete() {
-                uid_bytes[uid_idx..uid_idx + 3].copy_from_slice(&tx[3..6]);
+                uid_bytes
+                    .get_mut(uid_idx..uid_idx + 3)
+                    .ok_or(Error::InvalidResponse)?
+                    .copy_from_slice(&tx[3..6]);
                 uid_idx += 3;
                 cascade_level += 1;
             } else {
-                uid_bytes[uid_idx..uid_idx + 4].copy_from_slice(&tx[2..6]);
+                uid_bytes
+                    .get_mut(uid_idx..uid_idx + 4)
+                    .ok_or(Error::InvalidResponse)?
+                    .copy_from_slice(&tx[2..6]);
                 break 'cascade sak;
             }
         };
 
         match cascade_level {
             0 => Ok(Uid::Single(GenericUid {
-                bytes: uid_bytes[0..4].try_into().unwrap(),
+                bytes: uid_bytes[0..4].try_into().map_err(|_| Error::InvalidResponse)?,
                 sak,
             })),
             1 => Ok(Uid::Double(GenericUid {
-                bytes: uid_bytes[0..7].try_into().unwrap(),
+                bytes: uid_bytes[0..7].try_into().map_err(|_| Error::InvalidResponse)?,
                 sak,
             })),
             2 => Ok(Uid::Triple(GenericUid {
                 bytes: uid_bytes,
                 sak,
             })),
-            _ => unreachable!(),
+            _ => Err(Error::InvalidResponse),
         }
     }
 
-    /// Sends a Wake UP type A to nearby PICCs
-    pub fn communicate_to_picc<const RX: usize>(
-        &mut self,
-        // the data to be sent
-        tx_buffer: &[u8],
-        // number of bits in the last byte that will be transmitted
-        tx_last_bits: u8,
-        with_anti_collision: bool,
-        with_crc: bool,
-    ) -> Result<FifoData<RX>, Error<SPICS::SpiError, OPE>> {
-        info!("Communicate to picc {:x?}", tx_buffer);
-        self.setup_interrupt_mask(InterruptFlags::END_OF_RECEIVE)?;
-
-        self.execute_command(Command::Clear)?;
+    /// Activates a PICC in the field with REQA and select, fails with `Error::NoCard` when there is none
+    pub fn poll(&mut self) -> Result<Card, Error<SPICS::SpiError, OPE>> {
+        let atqa = self.reqa()?;
+        let uid = self.select_with_atqa(&atqa)?;
+        let sak = uid.sak();
+        Ok(Card { uid, atqa, sak })
+    }
 
-        let full_bytes_num = if tx_last_bits == 0 {
-            tx_buffer.len()
-        } else {
-            tx_buffer.len() - 1
-        };
+    /// Selects the PICC with the known `uid` without anticollision and returns its SAK.
+    /// The PICC has to be in READY state, after `reqa` or `wupa`.
+    pub fn select_uid(&mut self, uid: &Uid) -> Result<Sak, Error<SPICS::SpiError, OPE>> {
+        info!("Select {:x?}", uid.as_bytes());
+        let mut rest = uid.as_bytes();
+        for cmd in [picc::Command::SelCl1, picc::Command::SelCl2, picc::Command::SelCl3] {
+            let last = rest.len() <= 4;
+            let uid_cln = if last {
+                rest.try_into().map_err(|_| Error::InvalidResponse)?
+            } else {
+                // Incomplete UIDs are preceded by the cascade tag in every but the last level
+                let (head, tail) = rest.split_first_chunk::<3>().ok_or(Error::InvalidResponse)?;
+                rest = tail;
+                [picc::Command::CT as u8, head[0], head[1], head[2]]
+            };
+            let sak = self.send_select(cmd, uid_cln)?;
+            if sak.is_complete() != last {
+                return Err(Error::InvalidResponse);
+            }
+            if last {
+                return Ok(sak);
+            }
+        }
+        Err(Error::InvalidResponse)
+    }
 
-        let flags = (full_bytes_num << 6)
-            + (((tx_last_bits & 0x7) << 3) as usize)
-            + (with_anti_collision as usize);
+    /// Sends RATS to the selected ISO/IEC 14443-4 compliant PICC and returns its ATS.
+    ///
+    /// `fsdi` codes the largest frame the reader accepts, see `iso_dep::frame_size`,
+    /// `cid` is the card identifier assigned to the PICC, 0 when only one PICC is activated.
+    pub fn rats(&mut self, fsdi: u8, cid: u8) -> Result<Ats, Error<SPICS::SpiError, OPE>> {
+        if fsdi > iso_dep::MAX_FSI || cid > iso_dep::MAX_CID {
+            return Err(Error::InvalidArgument);
+        }
+        info!("RATS fsdi {} cid {}", fsdi, cid);
+        self.iso_dep = None;
+        let frame = [picc::Command::RATS as u8, fsdi << 4 | cid];
+        // The ATS may fill the whole frame the reader accepts, TL counts every byte but the CRC
+        let ats_len = iso_dep::frame_size(fsdi) - 2;
+        let timeout_ms = timing::timeout_ms(timing::ACTIVATION_FWT_US + frame_duration_us(ats_len));
+        let options = TransceiveOptions::new().timeout_ms(timeout_ms);
+        let rx = self.communicate_to_picc::<{ iso_dep::MAX_FRAME_LEN }>(&frame, options)?;
+        let ats = rx.buffer.get(..rx.valid_bytes).and_then(Ats::parse).ok_or(Error::InvalidResponse)?;
+        self.iso_dep = Some(Session::new(&ats, fsdi, cid));
+        // Nothing may be sent to the PICC before its start-up frame guard time passed
+        let sfgt_us = timing::sfgt_us(ats.sfgi);
+        if sfgt_us > 0 {
+            self.delay.delay_ms(timing::timeout_ms(sfgt_us));
+        }
+        Ok(ats)
+    }
 
-        self.write_register(Register::NumberOfTransmittedBytes0, flags as u8)?;
-        self.write_register(
-            Register::NumberOfTransmittedBytes1,
-            (full_bytes_num >> 2) as u8,
-        )?;
+    /// Block protocol state of the PICC activated with `rats`, `None` before activation
+    pub fn iso_dep_session(&self) -> Option<&Session> {
+        self.iso_dep.as_ref()
+    }
 
-        // Enable AGC (Useful in case the transponder is close to the reader)
-        self.write_register(Register::ReceiverConfiguration, 0x80)?;
+    /// Makes `session` the one `iso_dep_transceive` and `deselect` talk to and returns the
+    /// previous one. Several PICCs activated with distinct CIDs are addressed by swapping sessions
+    pub fn replace_iso_dep_session(&mut self, session: Option<Session>) -> Option<Session> {
+        core::mem::replace(&mut self.iso_dep, session)
+    }
 
-        if with_crc {
-            self.write_register(Register::ConfigurationRegister3, 0x0)?;
-        } else {
-            self.write_register(Register::ConfigurationRegister3, 0x80)?;
+    /// Sets the node address sent with every exchange of `iso_dep_transceive`, `None` omits it.
+    /// Fails with `Error::InvalidArgument` when the activated PICC doesn't support NAD
+    pub fn set_nad(&mut self, nad: Option<u8>) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        let session = self.iso_dep.as_mut().ok_or(Error::NotActivated)?;
+        if nad.is_some() && !session.nad_supported {
+            return Err(Error::InvalidArgument);
         }
+        session.nad = nad;
+        Ok(())
+    }
 
-        self.write_fifo(tx_buffer)?;
+    /// Exchanges `tx_buffer` with the PICC activated by `rats` in the ISO-DEP block protocol
+    /// and returns the length of its response in `rx_buffer`.
+    ///
+    /// Data longer than the frame size of the PICC is chained over several I-blocks, chained
+    /// responses are reassembled. Lost or corrupt blocks are recovered with R(NAK) and R(ACK)
+    /// up to `iso_dep::MAX_RETRANSMISSIONS` times.
+    pub fn iso_dep_transceive(
+        &mut self,
+        tx_buffer: &[u8],
+        rx_buffer: &mut [u8],
+    ) -> Result<usize, Error<SPICS::SpiError, OPE>> {
+        let mut session = self.iso_dep.ok_or(Error::NotActivated)?;
+        let result = self.iso_dep_exchange(&mut session, tx_buffer, rx_buffer);
+        // Block numbers advance with every acknowledged block, also when the exchange failed later
+        self.iso_dep = Some(session);
+        result
+    }
 
-        if with_crc {
-            self.execute_command(Command::TransmitWithCRC)?;
-        } else {
-            self.execute_command(Command::TransmitWithoutCRC)?;
+    fn iso_dep_exchange(
+        &mut self,
+        session: &mut Session,
+        tx_buffer: &[u8],
+        rx_buffer: &mut [u8],
+    ) -> Result<usize, Error<SPICS::SpiError, OPE>> {
+        let mut frame = [0u8; iso_dep::MAX_FRAME_LEN];
+        let mut block = [0u8; iso_dep::MAX_FRAME_LEN];
+        let mut chunks = tx_buffer.chunks(session.max_inf_len());
+        let mut chunk = chunks.next().unwrap_or_default();
+        let mut chaining = chunks.len() > 0;
+        // The NAD is only sent in the first block of a chain
+        let mut first = true;
+        let mut frame_len = session.i_block(chunk, chaining, first, &mut frame).ok_or(Error::NoRoom)?;
+        // The PICC is chaining its response, every block is acknowledged with R(ACK)
+        let mut receiving = false;
+        let mut received = 0;
+        let mut retransmissions = 0;
+        loop {
+            let tx_frame = frame.get(..frame_len).ok_or(Error::NoRoom)?;
+            let len = match self.send_block(session, tx_frame, &mut block) {
+                Ok(len) => len,
+                Err(Error::Timeout(_) | Error::Crc(..) | Error::Framing(..))
+                    if retransmissions < iso_dep::MAX_RETRANSMISSIONS =>
+                {
+                    debug!("Block lost, retransmission {}", retransmissions);
+                    retransmissions += 1;
+                    frame_len = session.r_block(receiving, &mut frame).ok_or(Error::NoRoom)?;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let (response, inf) = block.get(..len).and_then(|b| session.parse(b)).ok_or(Error::InvalidResponse)?;
+            match response {
+                Block::I {
+                    block_number,
+                    chaining: more,
+                } if !chaining && block_number == session.block_number => {
+                    session.block_number = !session.block_number;
+                    retransmissions = 0;
+                    let dst = rx_buffer.get_mut(received..received + inf.len()).ok_or(Error::NoRoom)?;
+                    dst.copy_from_slice(inf);
+                    received += inf.len();
+                    if !more {
+                        return Ok(received);
+                    }
+                    receiving = true;
+                    frame_len = session.r_block(true, &mut frame).ok_or(Error::NoRoom)?;
+                }
+                Block::RAck { block_number } if chaining && block_number == session.block_number => {
+                    session.block_number = !session.block_number;
+                    retransmissions = 0;
+                    chunk = chunks.next().unwrap_or_default();
+                    chaining = chunks.len() > 0;
+                    first = false;
+                    frame_len = session.i_block(chunk, chaining, first, &mut frame).ok_or(Error::NoRoom)?;
+                }
+                // The PICC missed the last block, send it again
+                Block::RAck { .. } if retransmissions < iso_dep::MAX_RETRANSMISSIONS => {
+                    retransmissions += 1;
+                    frame_len = if receiving {
+                        session.r_block(true, &mut frame)
+                    } else {
+                        session.i_block(chunk, chaining, first, &mut frame)
+                    }
+                    .ok_or(Error::NoRoom)?;
+                }
+                _ => return Err(Error::InvalidResponse),
+            }
         }
+    }
 
-        let intr = self.wait_for_interrupt(5)?;
+    /// Sends `apdu` to the PICC activated by `rats` and returns its response, whose data is
+    /// stored in `rx_buffer`. Status words other than `apdu::SW_SUCCESS` are not an error,
+    /// check them with `ApduResponse::sw`.
+    pub fn exchange_apdu<'b>(
+        &mut self,
+        apdu: &Apdu,
+        rx_buffer: &'b mut [u8],
+    ) -> Result<ApduResponse<'b>, Error<SPICS::SpiError, OPE>> {
+        let mut command = [0u8; apdu::MAX_COMMAND_LEN];
+        let len = apdu.encode(&mut command).ok_or(Error::InvalidArgument)?;
+        let command = command.get(..len).ok_or(Error::NoRoom)?;
+        debug!("APDU {:x?}", command);
+        let received = self.iso_dep_transceive(command, rx_buffer)?;
+        let response = rx_buffer.get(..received).ok_or(Error::NoRoom)?;
+        ApduResponse::parse(response).ok_or(Error::InvalidResponse)
+    }
 
-        if intr.contains(InterruptFlags::BIT_COLLISION) {
-            return Err(Error::Collision);
+    /// Selects the application `aid` on the PICC activated by `rats` with SELECT by DF name
+    /// and returns its FCI, stored in `rx_buffer`. Fails with `Error::Status` when the PICC
+    /// rejects the selection, e.g. 6A82 for an application that doesn't exist
+    pub fn select_application<'b>(
+        &mut self,
+        aid: &[u8],
+        rx_buffer: &'b mut [u8],
+    ) -> Result<Fci<'b>, Error<SPICS::SpiError, OPE>> {
+        if aid.is_empty() || aid.len() > apdu::MAX_AID_LEN {
+            return Err(Error::InvalidArgument);
         }
-
-        self.fifo_data()
+        info!("Select application {:x?}", aid);
+        // P1 selects by DF name, P2 the first or only occurrence returning the FCI
+        let select = Apdu::new(0x00, apdu::INS_SELECT, 0x04, 0x00).data(aid).le(256);
+        let response = self.exchange_apdu(&select, rx_buffer)?;
+        if !response.is_success() {
+            return Err(Error::Status(response.sw()));
+        }
+        Ok(Fci::parse(response.data))
     }
 
-    fn fifo_data<const RX: usize>(&mut self) -> Result<FifoData<RX>, Error<SPICS::SpiError, OPE>> {
-        let mut buffer = [0u8; RX];
-        let mut valid_bytes: usize = 0;
-
-        if RX > 0 {
-            let fifo_status = self.read_register(Register::FIFOStatus)?;
-
-            valid_bytes = (fifo_status >> 2) as usize;
-            if valid_bytes > RX {
-                return Err(Error::NoRoom);
-            }
-            if valid_bytes > 0 {
-                self.read_fifo(&mut buffer[0..valid_bytes])?;
+    /// Sends S(DESELECT) to the PICC activated by `rats`, which enters HALT state on confirmation.
+    /// Another PICC can be selected and activated afterwards.
+    pub fn deselect(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        let session = self.iso_dep.ok_or(Error::NotActivated)?;
+        info!("DESELECT");
+        let mut frame = [0u8; 2];
+        let len = session.deselect(&mut frame).ok_or(Error::NoRoom)?;
+        let request = frame.get(..len).ok_or(Error::NoRoom)?;
+        let timeout_ms = timing::timeout_ms(timing::DESELECT_FWT_US + timing::DELTA_FWT_US);
+        let options = TransceiveOptions::new().timeout_ms(timeout_ms);
+        let mut retransmissions = 0;
+        loop {
+            match self.communicate_to_picc::<2>(request, options) {
+                Ok(rx) if rx.buffer.get(..rx.valid_bytes) == Some(request) => break,
+                Ok(_) => return Err(Error::InvalidResponse),
+                Err(Error::Timeout(_) | Error::Crc(..) | Error::Framing(..))
+                    if retransmissions < iso_dep::MAX_RETRANSMISSIONS =>
+                {
+                    retransmissions += 1;
+                }
+                Err(e) => return Err(e),
             }
         }
+        self.iso_dep = None;
+        Ok(())
+    }
 
-        Ok(FifoData {
-            buffer,
-            valid_bytes,
-        })
+    /// Sends one block of the ISO-DEP protocol and reads the response block into `rx_buffer`.
+    /// The PICC has the frame waiting time of its ATS to respond, at least `Timeouts::transceive_ms`.
+    /// S(WTX) requests extending it are answered until the PICC responds or `Timeouts::wtx_max_ms` is used up.
+    fn send_block(
+        &mut self,
+        session: &Session,
+        frame: &[u8],
+        rx_buffer: &mut [u8],
+    ) -> Result<usize, Error<SPICS::SpiError, OPE>> {
+        let timeout_ms = timing::iso_dep_timeout_ms(session.fwi).max(self.config.timeouts.transceive_ms);
+        let options = TransceiveOptions::new().timeout_ms(timeout_ms);
+        let mut received = self.transceive_frame(frame, rx_buffer, options)?;
+        let mut extended_ms: u32 = 0;
+        let mut response = [0u8; 3];
+        loop {
+            let block = rx_buffer.get(..received.bytes).ok_or(Error::NoRoom)?;
+            let inf = match session.parse(block) {
+                Some((Block::SWtx, inf)) => inf,
+                _ => return Ok(received.bytes),
+            };
+            let (len, wtxm) = session.wtx_response(inf, &mut response).ok_or(Error::InvalidResponse)?;
+            let wtx = response.get(..len).ok_or(Error::NoRoom)?;
+            // The extended frame waiting time only applies to the response to S(WTX),
+            // ISO/IEC 14443-4 limits it to the frame waiting time of FWI 14
+            let timeout_ms = options
+                .timeout_ms
+                .saturating_mul(wtxm as u16)
+                .min(timing::iso_dep_timeout_ms(timing::MAX_FWI));
+            extended_ms += timeout_ms as u32;
+            if extended_ms > self.config.timeouts.wtx_max_ms as u32 {
+                self.count(|stats| &mut stats.timeouts);
+                return Err(Error::Timeout(self.debug_snapshot(received.interrupts)?));
+            }
+            debug!("S(WTX) {}, wait {}ms", wtxm, timeout_ms);
+            received = self.transceive_frame(wtx, rx_buffer, options.timeout_ms(timeout_ms))?;
+        }
     }
 
-    pub fn setup_interrupt_mask(&mut self, flags: InterruptFlags) -> Result<u8, Error<SPICS::SpiError, OPE>> {
-        // Need to invert bits
-        self.write_register(Register::MaskInterrupt, !flags.bits())?;
-        // Clear interrupts
-        self.read_register(Register::Interrupt)
+    /// Sends a PPS request to the PICC activated with `cid` and switches the chip to `tx`
+    /// from reader to PICC and `rx` from PICC to reader once the PICC confirmed it.
+    /// Fails with `Error::InvalidArgument` when `ats` doesn't advertise the bit rates.
+    ///
+    /// The rates may differ when the PICC allows it, see `Ats::fastest_bit_rates`. The PPS
+    /// response still arrives at the old rate, the receiver is switched before the next frame.
+    pub fn pps(&mut self, ats: &Ats, cid: u8, tx: BitRate, rx: BitRate) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        if cid > iso_dep::MAX_CID || !ats.supports_bit_rates(tx, rx) {
+            return Err(Error::InvalidArgument);
+        }
+        info!("PPS cid {} tx {:?} rx {:?}", cid, tx, rx);
+        let ppss = iso_dep::PPSS | cid;
+        // PPS0 announces PPS1, which holds DSI (PICC to reader) and DRI (reader to PICC)
+        let frame = [ppss, 0x11, (rx as u8) << 2 | tx as u8];
+        let options = TransceiveOptions::new().frame_waiting_time(ats.fwi);
+        let rx_frame = self.communicate_to_picc::<1>(&frame, options)?;
+        if rx_frame.valid_bytes != 1 || rx_frame.buffer[0] != ppss {
+            return Err(Error::InvalidResponse);
+        }
+        self.set_bit_rate(tx, rx)
     }
 
-    pub fn execute_command(&mut self, command: Command) -> Result<(), Error<SPICS::SpiError, OPE>> {
-        debug!("Executing command: {:?}", command);
-        self.write(&[command.command_pattern()])
+    /// Sends the SELECT frame of one cascade level with the complete UID CLn and returns the SAK
+    fn send_select(&mut self, cmd: picc::Command, uid_cln: [u8; 4]) -> Result<picc::Sak, Error<SPICS::SpiError, OPE>> {
+        let bcc = uid_cln.iter().fold(0, |bcc, byte| bcc ^ byte);
+        // NVB: 7 valid bytes
+        let [uid0, uid1, uid2, uid3] = uid_cln;
+        let frame = [cmd as u8, 0x70, uid0, uid1, uid2, uid3, bcc];
+        let options = TransceiveOptions::new().timeout_ms(self.config.timeouts.anticollision_ms);
+        let rx = self.communicate_to_picc::<1>(&frame, options)?;
+        Ok(picc::Sak::from(rx.buffer[0]))
     }
 
-    pub fn write_register(&mut self, reg: Register, val: u8) -> Result<(), Error<SPICS::SpiError, OPE>> {
-        debug!("Write register {:?} value: 0b{:08b}", reg, val);
-        self.write(&[reg.write_address(), val])
+    /// Returns default `TransceiveOptions` with the configured `Timeouts::transceive_ms`
+    pub fn transceive_options(&self) -> TransceiveOptions {
+        TransceiveOptions::new().timeout_ms(self.config.timeouts.transceive_ms)
     }
 
-    pub fn read_register(&mut self, reg: Register) -> Result<u8, Error<SPICS::SpiError, OPE>> {
-        let mut buffer = [reg.read_address(), 0];
+    /// Sets the response timeouts, see `Config::timeouts`
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.config.timeouts = timeouts;
+    }
 
-        self.spi_with_custom_cs.with_cs_high(&mut self.cs,|spi| {
-            let buffer = spi.transfer(&mut buffer)?;
-            debug!("Read register {:?} got value value: 0b{:08b}", reg, buffer[1]);
+    /// Transmits `tx_buffer` to the PICC and reads its response into a fixed size buffer,
+    /// see `transceive`. Errors are part of the anticollision and HLTA flow, so neither
+    /// retries nor recovery are run.
+    pub fn communicate_to_picc<const RX: usize>(
+        &mut self,
+        tx_buffer: &[u8],
+        options: TransceiveOptions,
+    ) -> Result<FifoData<RX>, Error<SPICS::SpiError, OPE>> {
+        let mut buffer = [0u8; RX];
+        let received = self.transceive_frame(tx_buffer, &mut buffer, options)?;
+        Ok(FifoData {
+            buffer,
+            valid_bytes: received.bytes,
+            last_byte_bits: received.last_byte_bits,
+        })
+    }
 
-            Ok(buffer[1])
-        }).map_err(Error::SpiWithCS)
+    /// Transmits the first `tx_bits` bits of `tx_buffer` without CRC and reads the response
+    /// into `rx_buffer`. Returns the number of received bits.
+    ///
+    /// Bits are sent LSB first, an incomplete last byte sends its low bits.
+    /// Parity is still generated and checked according to the ISO-14443A configuration.
+    pub fn transceive_bits(
+        &mut self,
+        tx_buffer: &[u8],
+        tx_bits: usize,
+        rx_buffer: &mut [u8],
+    ) -> Result<usize, Error<SPICS::SpiError, OPE>> {
+        let tx_frame = tx_buffer.get(..tx_bits.div_ceil(8)).ok_or(Error::NoRoom)?;
+        let options = self.transceive_options().crc(false).tx_last_bits((tx_bits % 8) as u8);
+        let received = self.transceive(tx_frame, rx_buffer, options)?;
+        Ok(received.bits())
+    }
+
+    /// Transmits the 7 low bits of `byte` as a short frame, without parity and CRC, and reads
+    /// the response into `rx_buffer`. `options` set the timeout and receive framing, CRC and
+    /// `tx_last_bits` are overridden. Returns the number of received bits.
+    ///
+    /// Responses are received as complete bytes, 4 bit answers like the ACK of gen1a magic cards
+    /// need `TransceiveOptions::four_bit_response`. REQA and WUPA are better sent with `reqa`
+    /// and `wupa`, which use the dedicated commands.
+    pub fn send_short_frame(
+        &mut self,
+        byte: u8,
+        rx_buffer: &mut [u8],
+        options: TransceiveOptions,
+    ) -> Result<usize, Error<SPICS::SpiError, OPE>> {
+        info!("Short frame {:x}", byte & 0x7F);
+        let options = options.crc(false).tx_last_bits(7);
+        let received = self.transceive(&[byte & 0x7F], rx_buffer, options)?;
+        Ok(received.bits())
+    }
+
+    /// Transmits `tx_buffer` to the PICC and returns its response as an owned vector,
+    /// together with the number of valid bits in the last byte. See `transceive`
+    #[cfg(feature = "heapless")]
+    pub fn transceive_heapless<const N: usize>(
+        &mut self,
+        tx_buffer: &[u8],
+        options: TransceiveOptions,
+    ) -> Result<(heapless::Vec<u8, N>, u8), Error<SPICS::SpiError, OPE>> {
+        let mut rx = heapless::Vec::new();
+        // Can't fail, the length is the capacity
+        let _ = rx.resize_default(N);
+        let received = self.transceive(tx_buffer, &mut rx, options)?;
+        rx.truncate(received.bytes);
+        Ok((rx, received.last_byte_bits))
+    }
+
+    /// Brings the chip back to a known state after a failed operation: clears the FIFO,
+    /// unmasks receive data, resets the interrupt mask and drops pending and latched interrupts.
+    /// With `cycle_field` the field is switched off for 5ms, resetting all PICCs to IDLE.
+    pub fn recover(&mut self, cycle_field: bool) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        info!("Recover");
+        self.count(|stats| &mut stats.recoveries);
+        self.execute_command(Command::Clear)?;
+        self.unmask_receive()?;
+        self.setup_interrupt_mask(InterruptFlags::END_OF_RECEIVE)?;
+        self.latched = InterruptFlags::empty();
+        if cycle_field {
+            self.field_off()?;
+            self.delay.delay_ms(FIELD_RESET_MS);
+            self.field_on()?;
+        }
+        Ok(())
+    }
+
+    /// Runs `operation` until it succeeds, fails with an error that isn't retryable
+    /// or `Config::retry` ran out of attempts
+    fn with_retry<T, F>(&mut self, mut operation: F) -> Result<T, Error<SPICS::SpiError, OPE>>
+    where
+        F: FnMut(&mut Self) -> Result<T, Error<SPICS::SpiError, OPE>>,
+    {
+        let policy = self.config.retry;
+        let mut delay_ms = policy.delay_ms;
+        let mut attempt = 1;
+        loop {
+            match operation(self) {
+                Err(e) if attempt < policy.max_attempts && policy.is_retryable(&e) => {
+                    debug!("Attempt {} failed, retry in {}ms", attempt, delay_ms);
+                    self.delay.delay_ms(delay_ms);
+                    delay_ms = delay_ms.saturating_mul(policy.backoff_factor as u16);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Sets the unmodulated carrier time after `field_on`, see `Config::field_guard_ms`
+    pub fn set_field_guard_time(&mut self, guard_ms: u16) {
+        self.config.field_guard_ms = guard_ms;
+    }
+
+    /// Sets the anticollision frames per cascade level, see `Config::anticollision_max_loops`
+    pub fn set_anticollision_max_loops(&mut self, max_loops: u8) {
+        self.config.anticollision_max_loops = max_loops;
+    }
+
+    /// Sets the retries of `reqa`, `select` and `transceive`, see `Config::retry`
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.config.retry = policy;
+    }
+
+    /// Counters collected while `Config::collect_stats` is set
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = Stats::default();
+    }
+
+    /// Enables or disables collection of `stats`, see `Config::collect_stats`
+    pub fn set_collect_stats(&mut self, enable: bool) {
+        self.config.collect_stats = enable;
+    }
+
+    fn count(&mut self, counter: fn(&mut Stats) -> &mut u32) {
+        if self.config.collect_stats {
+            let counter = counter(&mut self.stats);
+            *counter = counter.saturating_add(1);
+        }
+    }
+
+    /// Sets the recovery run after a failed transceive, see `Config::recovery`
+    pub fn set_recovery(&mut self, recovery: Recovery) {
+        self.config.recovery = recovery;
+    }
+
+    /// Transmits `tx_buffer` to the PICC and reads its response into `rx_buffer`. Frames larger
+    /// than the FIFO are streamed on water level interrupts, up to 1023 bytes.
+    /// Fails with `Error::NoRoom` when the response doesn't fit.
+    ///
+    /// Failures are retried according to `Config::retry`, unless `Config::recovery` is
+    /// `Recovery::CycleField`: the PICC is back in IDLE after the recovery and won't answer the frame.
+    pub fn transceive(
+        &mut self,
+        tx_buffer: &[u8],
+        rx_buffer: &mut [u8],
+        options: TransceiveOptions,
+    ) -> Result<Received, Error<SPICS::SpiError, OPE>> {
+        if self.config.recovery == Recovery::CycleField {
+            return self.transceive_once(tx_buffer, rx_buffer, options);
+        }
+        self.with_retry(|as3910| as3910.transceive_once(tx_buffer, rx_buffer, options))
+    }
+
+    /// Single transceive attempt, followed by the configured recovery when it failed
+    fn transceive_once(
+        &mut self,
+        tx_buffer: &[u8],
+        rx_buffer: &mut [u8],
+        options: TransceiveOptions,
+    ) -> Result<Received, Error<SPICS::SpiError, OPE>> {
+        let result = self.transceive_frame(tx_buffer, rx_buffer, options);
+        if let Err(e) = &result {
+            let recoverable = matches!(
+                e,
+                Error::Collision(..)
+                    | Error::Crc(..)
+                    | Error::Framing(..)
+                    | Error::InterruptTimeout
+                    | Error::Timeout(_)
+                    | Error::FifoOverflow
+                    | Error::IncompleteFrame
+            );
+            if recoverable && self.config.recovery != Recovery::Disabled {
+                self.recover(self.config.recovery == Recovery::CycleField)?;
+            }
+        }
+        result
+    }
+
+    fn transceive_frame(
+        &mut self,
+        tx_buffer: &[u8],
+        rx_buffer: &mut [u8],
+        options: TransceiveOptions,
+    ) -> Result<Received, Error<SPICS::SpiError, OPE>> {
+        info!("Communicate to picc {:x?}", tx_buffer);
+        if self.config.min_supply_mv > 0 {
+            let supply = self.measure_supply()?;
+            if supply.0 < self.config.min_supply_mv {
+                return Err(Error::SupplyLow(supply));
+            }
+        }
+        let (first_chunk, mut remaining) = tx_buffer.split_at(tx_buffer.len().min(FIFO_SIZE));
+        let mut interrupts = InterruptFlags::empty();
+        // Responses larger than the FIFO are drained every time it fills above the water level
+        let stream_rx = rx_buffer.len() > FIFO_SIZE;
+        let mut receive_flags = InterruptFlags::END_OF_RECEIVE;
+        if stream_rx {
+            receive_flags |= InterruptFlags::FIFO_WATER_LEVEL;
+        }
+        self.setup_interrupt_mask(
+            InterruptFlags::END_OF_TRANSMISSION | InterruptFlags::FIFO_WATER_LEVEL | InterruptFlags::END_OF_RECEIVE,
+        )?;
+
+        self.execute_command(Command::Clear)?;
+
+        self.set_transmit_length(
+            tx_buffer.len(),
+            options.tx_last_bits,
+            options.anticollision,
+            options.four_bit_response,
+        )?;
+
+        // AGC enabled by default (Useful in case the transponder is close to the reader)
+        self.apply(self.receiver_config)?;
+
+        let no_parity_check =
+            self.iso14443a_config.no_parity_check || !options.parity_check || options.four_bit_response;
+        self.apply(
+            self.iso14443a_config
+                .receive_without_crc(!options.crc)
+                .no_parity_check(no_parity_check),
+        )?;
+
+        self.write_fifo(first_chunk)?;
+
+        if options.crc {
+            self.execute_command(Command::TransmitWithCRC)?;
+        } else {
+            self.execute_command(Command::TransmitWithoutCRC)?;
+        }
+
+        // The receive timeout only starts once the frame has left the antenna
+        let transmit_timeout_ms = ((frame_duration_us(tx_buffer.len()) / 1000) as u16).saturating_add(options.timeout_ms);
+        // Frames larger than the FIFO are refilled every time it drains below the water level
+        loop {
+            let transmit_flags = InterruptFlags::END_OF_TRANSMISSION | InterruptFlags::FIFO_WATER_LEVEL;
+            let intr = self.wait_for_flags(transmit_flags, WaitMode::Any, transmit_timeout_ms)?;
+            interrupts |= intr;
+            if intr.contains(InterruptFlags::FIFO_WATER_LEVEL) && !remaining.is_empty() {
+                let room = FIFO_SIZE - self.fifo_len()?;
+                let (chunk, rest) = remaining.split_at(remaining.len().min(room));
+                self.write_fifo(chunk)?;
+                remaining = rest;
+            }
+            if intr.contains(InterruptFlags::END_OF_TRANSMISSION) {
+                if !remaining.is_empty() {
+                    return Err(Error::IncompleteFrame);
+                }
+                break;
+            }
+        }
+        // Switch to the receive interrupts, without clearing flags that are already pending
+        self.write_interrupt_mask(receive_flags)?;
+
+        let mask_window_us = self.config.receive_mask_window_us.min(timing::MAX_RECEIVE_MASK_WINDOW_US);
+        if mask_window_us > 0 && !interrupts.contains(InterruptFlags::END_OF_RECEIVE) {
+            self.mask_receive()?;
+            self.delay.delay_us(mask_window_us);
+            self.unmask_receive()?;
+        }
+
+        let mut valid_bytes = 0;
+        // A fast response may already have been reported together with the end of transmission
+        while !interrupts.contains(InterruptFlags::END_OF_RECEIVE) {
+            let intr = match self.wait_for_flags(receive_flags, WaitMode::Any, options.timeout_ms) {
+                // The frame was sent, the PICC didn't answer
+                Err(Error::InterruptTimeout) => {
+                    self.count(|stats| &mut stats.timeouts);
+                    return Err(Error::Timeout(self.debug_snapshot(interrupts)?));
+                }
+                result => result?,
+            };
+            interrupts |= intr;
+
+            let water_level_only = intr.contains(InterruptFlags::FIFO_WATER_LEVEL)
+                && !intr.contains(InterruptFlags::END_OF_RECEIVE);
+            if stream_rx && water_level_only {
+                self.drain_fifo(rx_buffer, &mut valid_bytes)?;
+            }
+        }
+        if interrupts.contains(InterruptFlags::BIT_COLLISION) {
+            let position = CollisionPosition::from_register(self.read_register(Register::Collision)?);
+            self.count(|stats| &mut stats.collisions);
+            return Err(Error::Collision(position, self.debug_snapshot(interrupts)?));
+        }
+        if !rx_buffer.is_empty() {
+            self.drain_fifo(rx_buffer, &mut valid_bytes)?;
+        }
+        let received = Received {
+            bytes: valid_bytes,
+            last_byte_bits: if options.four_bit_response { 4 } else { 8 },
+            interrupts,
+        };
+        if interrupts.contains(InterruptFlags::RECEIVE_DATA_CODING_ERROR) {
+            self.count(|stats| &mut stats.framing_errors);
+            return Err(Error::Framing(received, self.debug_snapshot(interrupts)?));
+        }
+        if options.crc && interrupts.contains(InterruptFlags::CRC_ERROR) {
+            self.count(|stats| &mut stats.crc_errors);
+            return Err(Error::Crc(received, self.debug_snapshot(interrupts)?));
+        }
+
+        if let Some(adaptive_power) = self.config.adaptive_power {
+            self.adapt_output_power(adaptive_power)?;
+        }
+
+        if self.config.auto_squelch {
+            // Adapt gain to the noise level present now that the PICC is done responding
+            self.clear_squelch()?;
+            self.squelch()?;
+        }
+
+        Ok(received)
+    }
+
+    /// Sends `tx_buffer` with CRC in NFC mode, after the Initial RF Collision Avoidance
+    /// which checks that no other device emits a field before switching on our own.
+    /// The receiver stays armed for the target response. Fails with `Error::InvalidArgument` outside NFC mode.
+    pub fn transmit_with_initial_rfca(&mut self, tx_buffer: &[u8]) -> Result<RfcaOutcome, Error<SPICS::SpiError, OPE>> {
+        info!("NFC transmit with initial RFCA {:x?}", tx_buffer);
+        self.nfc_transmit(
+            Command::NFCTransmitWithInitialRFCollisionAvoidance,
+            tx_buffer,
+            nfc::INITIAL_DELAY_US + nfc::INITIAL_GUARD_US,
+        )
+    }
+
+    /// Sends `tx_buffer` with CRC in NFC mode as a response, after the Response RF Collision Avoidance
+    /// which waits for the initiator field to be switched off.
+    /// The receiver stays armed for the next request. Fails with `Error::InvalidArgument` outside NFC mode.
+    pub fn transmit_with_response_rfca(
+        &mut self,
+        tx_buffer: &[u8],
+        collision_avoidance: ResponseCollisionAvoidance,
+    ) -> Result<RfcaOutcome, Error<SPICS::SpiError, OPE>> {
+        info!("NFC transmit with response RFCA {:x?}", tx_buffer);
+        let command = match collision_avoidance {
+            ResponseCollisionAvoidance::Random => Command::NFCTransmitWithResponseRFCollisionAvoidance,
+            ResponseCollisionAvoidance::N0 => Command::NFCTransmitWithResponseRFCollisionAvoidanceWithN0,
+        };
+        self.nfc_transmit(command, tx_buffer, collision_avoidance.max_duration_us())
+    }
+
+    /// Enables the Target Activation Detector and the NFC event interrupt, so the appearance
+    /// and disappearance of an external field is reported. Fails with `Error::InvalidArgument`
+    /// outside NFC mode, see `set_mode`.
+    pub fn enable_field_detection(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.require_nfc_mode()?;
+        self.modify_register(Register::OperationControl, |val| {
+            (OperationControlFlags::from_bits_truncate(val) | OperationControlFlags::NFC_T).bits()
+        })?;
+        self.persistent_interrupts |= InterruptFlags::NFC_EVENT;
+        // Mask bits are inverted, clearing the bit enables the source
+        self.modify_register(Register::MaskInterrupt, |val| val & !InterruptFlags::NFC_EVENT.bits())
+    }
+
+    /// Disables the Target Activation Detector and masks the NFC event interrupt.
+    /// Allowed in every mode, so detection can still be switched off after leaving NFC mode.
+    pub fn disable_field_detection(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.modify_register(Register::OperationControl, |val| {
+            (OperationControlFlags::from_bits_truncate(val) - OperationControlFlags::NFC_T).bits()
+        })?;
+        self.persistent_interrupts -= InterruptFlags::NFC_EVENT;
+        self.modify_register(Register::MaskInterrupt, |val| val | InterruptFlags::NFC_EVENT.bits())
+    }
+
+    /// Decodes an NFC event contained in `flags`. The interrupt fires on every change of the
+    /// detector output, the current state is read from the Receiver State Display Register.
+    pub fn field_event(
+        &mut self,
+        flags: InterruptFlags,
+    ) -> Result<Option<ExternalFieldEvent>, Error<SPICS::SpiError, OPE>> {
+        if !flags.contains(InterruptFlags::NFC_EVENT) {
+            return Ok(None);
+        }
+        let event = if self.receiver_state()?.oscillator_ok_or_rf_present {
+            ExternalFieldEvent::FieldDetected
+        } else {
+            ExternalFieldEvent::FieldLost
+        };
+        debug!("External field event {:?}", event);
+        Ok(Some(event))
+    }
+
+    /// Waits up to `timeout_in_ms` for the external field to appear or disappear,
+    /// see `enable_field_detection`. Fails with `Error::InvalidArgument` outside NFC mode.
+    pub fn wait_for_field_event(
+        &mut self,
+        timeout_in_ms: u16,
+    ) -> Result<ExternalFieldEvent, Error<SPICS::SpiError, OPE>> {
+        self.require_nfc_mode()?;
+        let flags = self.wait_for_flags(InterruptFlags::NFC_EVENT, WaitMode::Any, timeout_in_ms)?;
+        match self.field_event(flags)? {
+            Some(event) => Ok(event),
+            None => Err(Error::InterruptTimeout),
+        }
+    }
+
+    /// Runs one of the NFC transmit commands and waits until the field is switched off again
+    /// or an external field prevented the transmission
+    fn nfc_transmit(
+        &mut self,
+        command: Command,
+        tx_buffer: &[u8],
+        collision_avoidance_us: u32,
+    ) -> Result<RfcaOutcome, Error<SPICS::SpiError, OPE>> {
+        self.require_nfc_mode()?;
+        self.setup_interrupt_mask(
+            InterruptFlags::END_OF_TRANSMISSION | InterruptFlags::NFC_EVENT | InterruptFlags::END_OF_RECEIVE,
+        )?;
+        self.execute_command(Command::Clear)?;
+        self.set_transmit_length(tx_buffer.len(), 0, false, false)?;
+        self.write_fifo(tx_buffer)?;
+        self.execute_command(command)?;
+
+        let timeout_ms = ((collision_avoidance_us + frame_duration_us(tx_buffer.len())) / 1000 + 2) as u16;
+        let intr = self.wait_for_interrupt(timeout_ms)?;
+        if intr.contains(InterruptFlags::NFC_EVENT) {
+            return Ok(RfcaOutcome::ExternalFieldDetected);
+        }
+        if !intr.contains(InterruptFlags::END_OF_TRANSMISSION) {
+            return Err(Error::InterruptTimeout);
+        }
+        Ok(RfcaOutcome::Transmitted)
+    }
+
+    /// Fails with `Error::InvalidArgument` unless the chip is in NFCIP-1 active mode, see `set_mode`
+    fn require_nfc_mode(&self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        if self.mode != OperatingMode::Nfcip1Active {
+            return Err(Error::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    /// Number of bytes currently in the FIFO. Fails with `Error::FifoOverflow` when data was
+    /// written to a full FIFO, the chip has no indication of underflow.
+    fn fifo_len(&mut self) -> Result<usize, Error<SPICS::SpiError, OPE>> {
+        let fifo_status = FifoStatusFlags::from_bits_truncate(self.read_register(Register::FIFOStatus)?);
+        if fifo_status.contains(FifoStatusFlags::FIFO_OVR) {
+            return Err(Error::FifoOverflow);
+        }
+        Ok(((fifo_status & FifoStatusFlags::FIFO_B).bits() >> 2) as usize)
+    }
+
+    /// Programs the Number of Transmitted Bytes Registers. `tx_last_bits` is the number of bits
+    /// of the last byte to transmit, 0 when it is complete. The 10 bit count of complete bytes
+    /// is split into `ntx1..0` in register 0x0B and `ntx9..2` in register 0x0C.
+    /// `four_bit_response` makes the receiver expect a 4 bit frame.
+    fn set_transmit_length(
+        &mut self,
+        len: usize,
+        tx_last_bits: u8,
+        with_anti_collision: bool,
+        four_bit_response: bool,
+    ) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        let full_bytes_num = if tx_last_bits == 0 || len == 0 {
+            len
+        } else {
+            len - 1
+        };
+        if full_bytes_num > MAX_FRAME_LEN {
+            return Err(Error::FrameTooLong);
+        }
+
+        let mut flags = NumberOfTransmittedBytes0Flags::from_bits_truncate(
+            ((full_bytes_num & 0b11) << 6) as u8 | ((tx_last_bits & 0b111) << 3),
+        );
+        flags.set(NumberOfTransmittedBytes0Flags::ANTCL, with_anti_collision);
+        flags.set(NumberOfTransmittedBytes0Flags::FRM4, four_bit_response);
+
+        self.write_registers(
+            Register::NumberOfTransmittedBytes0,
+            &[flags.bits(), (full_bytes_num >> 2) as u8],
+        )
+    }
+
+    fn fifo_data<const RX: usize>(&mut self) -> Result<FifoData<RX>, Error<SPICS::SpiError, OPE>> {
+        let mut buffer = [0u8; RX];
+        let mut valid_bytes: usize = 0;
+
+        if RX > 0 {
+            self.drain_fifo(&mut buffer, &mut valid_bytes)?;
+        }
+
+        Ok(FifoData {
+            buffer,
+            valid_bytes,
+            last_byte_bits: 8,
+        })
+    }
+
+    /// Reads the bytes currently in the FIFO into `buffer` after the first `valid_bytes`
+    fn drain_fifo(&mut self, buffer: &mut [u8], valid_bytes: &mut usize) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        let len = self.fifo_len()?;
+        let chunk = buffer.get_mut(*valid_bytes..*valid_bytes + len).ok_or(Error::NoRoom)?;
+        if len > 0 {
+            self.read_fifo(chunk)?;
+            *valid_bytes += len;
+        }
+        Ok(())
+    }
+
+    /// Captures the registers describing a failed exchange which raised `interrupts`,
+    /// empty without the `debug-snapshots` feature
+    #[cfg(feature = "debug-snapshots")]
+    fn debug_snapshot(&mut self, interrupts: InterruptFlags) -> Result<DebugSnapshot, Error<SPICS::SpiError, OPE>> {
+        Ok(DebugSnapshot {
+            fifo_status: self.read_register(Register::FIFOStatus)?,
+            interrupts: interrupts | self.read_interrupts()?,
+            collision: self.read_register(Register::Collision)?,
+            receiver_state: self.read_register(Register::ReceiverStateDisplay)?,
+        })
+    }
+
+    #[cfg(not(feature = "debug-snapshots"))]
+    fn debug_snapshot(&mut self, _interrupts: InterruptFlags) -> Result<DebugSnapshot, Error<SPICS::SpiError, OPE>> {
+        Ok(DebugSnapshot {})
+    }
+
+    /// Reads all known registers (except Interrupt register, which clears on read)
+    /// to capture chip state for diagnostics, together with the cached values
+    /// when `Config::cache_registers` is set.
+    pub fn dump_registers(&mut self) -> Result<RegisterDump, Error<SPICS::SpiError, OPE>> {
+        // Two bursts to skip over the Interrupt register (0x08)
+        let mut low = [0u8; 8];
+        let mut high = [0u8; 15];
+        self.read_registers(Register::ModeDefinition, &mut low)?;
+        self.read_registers(Register::FIFOStatus, &mut high)?;
+
+        Ok(RegisterDump {
+            mode_definition: low[0],
+            operation_control: low[1],
+            configuration_register2: low[2],
+            configuration_register3: low[3],
+            configuration_register4: low[4],
+            configuration_register5: low[5],
+            receiver_configuration: low[6],
+            mask_interrupt: low[7],
+            fifo_status: high[0],
+            collision: high[1],
+            number_of_transmitted_bytes0: high[2],
+            number_of_transmitted_bytes1: high[3],
+            ad_converter_output: high[4],
+            antenna_calibration: high[5],
+            external_trim: high[6],
+            modular_depth_definition: high[7],
+            modular_depth_display: high[8],
+            antenna_driver_am_modulated_level_definition: high[9],
+            antenna_driver_non_modulated_level_definition: high[10],
+            nfcip_field_detection_threshold: high[11],
+            regulators_display: high[12],
+            regulated_voltage_definition: high[13],
+            receiver_state_display: high[14],
+            cached: self.shadow,
+        })
+    }
+
+    /// Enables the interrupt sources in `flags` until the returned guard is dropped, which disables
+    /// the sources it added again. Pending flags are not cleared. The driver is used through the guard,
+    /// operations run meanwhile enable their own sources on top of the subscribed ones.
+    #[allow(clippy::type_complexity)]
+    pub fn subscribe(
+        &mut self,
+        flags: InterruptFlags,
+    ) -> Result<InterruptSubscription<'_, SPICS, CS, INTR, DELAY>, Error<SPICS::SpiError, OPE>> {
+        let added = flags - self.persistent_interrupts;
+        self.persistent_interrupts |= added;
+        self.write_interrupt_mask(InterruptFlags::empty())?;
+        Ok(InterruptSubscription { as3910: self, added })
+    }
+
+    /// Enables `flags` on top of the persistent sources and clears pending interrupts
+    fn setup_interrupt_mask(&mut self, flags: InterruptFlags) -> Result<u8, Error<SPICS::SpiError, OPE>> {
+        self.write_interrupt_mask(flags)?;
+        // Clear interrupts
+        Ok(self.read_interrupts()?.bits())
+    }
+
+    /// Enables `flags` on top of the persistent sources, masking every other source
+    fn write_interrupt_mask(&mut self, flags: InterruptFlags) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        // Need to invert bits
+        self.write_register(Register::MaskInterrupt, !(flags | self.persistent_interrupts).bits())
+    }
+
+    /// Returns all interrupt flags seen since the previous call and clears the latch.
+    ///
+    /// Reading the Interrupt Register clears it, so every read done by the driver is
+    /// accumulated here. Flags consumed by an earlier wait, or raised together with the
+    /// one a wait was looking for, are therefore still available afterwards.
+    pub fn take_interrupts(&mut self) -> InterruptFlags {
+        core::mem::replace(&mut self.latched, InterruptFlags::empty())
+    }
+
+    /// Reads (and clears) the Interrupt Register, adding the flags to the latch
+    fn read_interrupts(&mut self) -> Result<InterruptFlags, Error<SPICS::SpiError, OPE>> {
+        let flags = InterruptFlags::from_bits_truncate(self.read_register(Register::Interrupt)?);
+        self.latched |= flags;
+        Ok(flags)
+    }
+
+    pub fn execute_command(&mut self, command: Command) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        debug!("Executing command: {:?}", command);
+        self.write(&[command.command_pattern()])?;
+        self.trace(SpiEvent::Command(command));
+        if command.is_transmit() {
+            self.count(|stats| &mut stats.frames_sent);
+        }
+        if command == Command::SetDefault {
+            self.shadow = [None; REGISTER_COUNT];
+            self.mode = OperatingMode::default();
+            self.persistent_interrupts = InterruptFlags::empty();
+        }
+        Ok(())
+    }
+
+    /// Executes `command` and waits until it completed. With `completion` flags it waits for one of them
+    /// to be signalled, failing with `Error::InterruptTimeout` after `timeout_in_ms`. Commands
+    /// that do not signal completion pass empty flags and are given `timeout_in_ms` to finish.
+    pub fn execute_command_blocking(
+        &mut self,
+        command: Command,
+        completion: InterruptFlags,
+        timeout_in_ms: u16,
+    ) -> Result<InterruptFlags, Error<SPICS::SpiError, OPE>> {
+        if completion.is_empty() {
+            self.execute_command(command)?;
+            self.delay.delay_ms(timeout_in_ms);
+            return Ok(completion);
+        }
+
+        self.setup_interrupt_mask(completion)?;
+        self.execute_command(command)?;
+        let flags = self.wait_for_interrupt(timeout_in_ms)?;
+        if !flags.intersects(completion) {
+            return Err(Error::InterruptTimeout);
+        }
+        Ok(flags)
+    }
+
+    /// Executes a command which does not signal completion and waits for its execution time
+    fn execute_timed_command(&mut self, command: Command) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.execute_command_blocking(command, InterruptFlags::empty(), command.execution_time_ms())?;
+        Ok(())
+    }
+
+    pub fn write_register(&mut self, reg: Register, val: u8) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        debug!("Write register {:?} value: 0b{:08b}", reg, val);
+        self.write(&[reg.write_address(), val])?;
+        self.trace(SpiEvent::WriteRegister { reg, value: val });
+        if let Some(shadow) = self.shadow.get_mut(reg as usize).filter(|_| self.config.cache_registers) {
+            *shadow = Some(val);
+        }
+        if self.config.verify_writes {
+            self.verify_register(reg, val)?;
+        }
+        Ok(())
+    }
+
+    /// Sets the largest SPI write issued for FIFO loads, see `Config::spi_chunk_size`
+    pub fn set_spi_chunk_size(&mut self, chunk_size: usize) {
+        self.config.spi_chunk_size = chunk_size;
+    }
+
+    /// Enables or disables read back verification of register writes
+    pub fn set_verify_writes(&mut self, verify: bool) {
+        self.config.verify_writes = verify;
+    }
+
+    /// Enables or disables the shadow copy of written registers, see `Config::cache_registers`.
+    /// Disabling it drops the cached values.
+    pub fn set_register_cache(&mut self, enable: bool) {
+        self.config.cache_registers = enable;
+        if !enable {
+            self.shadow = [None; REGISTER_COUNT];
+        }
+    }
+
+    fn verify_register(&mut self, reg: Register, wrote: u8) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        let mask = reg.write_mask();
+        if mask == 0 {
+            return Ok(());
+        }
+        let read = self.read_register(reg)?;
+        if read & mask != wrote & mask {
+            return Err(Error::VerifyFailed { reg, wrote, read });
+        }
+        Ok(())
+    }
+
+    /// Returns the last value written to the register since reset, without any SPI traffic.
+    /// Useful for registers which can't be meaningfully read back mid-operation.
+    /// Always `None` unless `Config::cache_registers` is set.
+    pub fn cached(&self, reg: Register) -> Option<u8> {
+        self.shadow.get(reg as usize).copied().flatten()
+    }
+
+    /// Writes a typed register configuration
+    pub fn apply<C: RegisterConfig>(&mut self, config: C) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.write_register(C::REGISTER, config.bits())
+    }
+
+    /// Reads back a typed register configuration
+    pub fn read_config<C: RegisterConfig>(&mut self) -> Result<C, Error<SPICS::SpiError, OPE>> {
+        Ok(C::from_bits(self.read_register(C::REGISTER)?))
+    }
+
+    /// Selects automatic (Adjust regulators command) or external definition of regulated voltage
+    pub fn set_regulated_voltage(&mut self, voltage: RegulatedVoltage) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.apply(voltage)
+    }
+
+    /// Runs the Calibrate antenna command which adjusts the trim capacitors
+    /// so that the antenna LC tank is in resonance.
+    pub fn calibrate_antenna(&mut self) -> Result<AntennaCalibrationResult, Error<SPICS::SpiError, OPE>> {
+        self.execute_timed_command(Command::CalibrateAntenna)?;
+
+        let result = AntennaCalibrationResult::from(self.read_register(Register::AntennaCalibration)?);
+        info!("Antenna calibration {:?}", result);
+        Ok(result)
+    }
+
+    /// Selects whether the trim switches follow the Calibrate antenna result or a forced trim code
+    pub fn set_antenna_trim(&mut self, trim: AntennaTrim) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.apply(trim)
+    }
+
+    /// Reads the trim code found by the last Calibrate antenna command
+    pub fn calibrated_trim(&mut self) -> Result<u8, Error<SPICS::SpiError, OPE>> {
+        Ok(AntennaCalibrationResult::from(self.read_register(Register::AntennaCalibration)?).trim)
+    }
+
+    /// Periodic maintenance against temperature and supply drift, meant to be called
+    /// between polls. Recalibrates the antenna when the resonance is out of `RESONANCE_TOLERANCE`
+    /// (unless a manual trim is forced) and re-adjusts automatically defined regulators.
+    pub fn maintenance(&mut self) -> Result<MaintenanceReport, Error<SPICS::SpiError, OPE>> {
+        let resonance = self.check_antenna_resonance()?;
+        let antenna_recalibrated = if resonance.needs_recalibration()
+            && self.read_config::<AntennaTrim>()? == AntennaTrim::Calibrated
+        {
+            Some(self.calibrate_antenna()?)
+        } else {
+            None
+        };
+
+        let regulators_adjusted = if self.read_config::<RegulatedVoltage>()? == RegulatedVoltage::Automatic {
+            let before = self.regulator_status()?.voltage;
+            let after = self.adjust_regulators()?.voltage;
+            if after != before { after } else { None }
+        } else {
+            None
+        };
+
+        let report = MaintenanceReport {
+            resonance,
+            antenna_recalibrated,
+            regulators_adjusted,
+        };
+        info!("Maintenance {:?}", report);
+        Ok(report)
+    }
+
+    /// Captures the current antenna trim, modulation depth and regulator settings
+    pub fn capture_calibration_profile(&mut self) -> Result<CalibrationProfile, Error<SPICS::SpiError, OPE>> {
+        let trim = match self.read_config::<AntennaTrim>()? {
+            AntennaTrim::Manual(code) => code,
+            AntennaTrim::Calibrated => self.calibrated_trim()?,
+        };
+        let am_modulated_level = match self.read_config::<ModulationDepthConfig>()? {
+            ModulationDepthConfig::Manual => self.read_register(Register::AntennaDriverAMModulatedLevelDefinition)?,
+            ModulationDepthConfig::Calibrated { .. } => self.read_register(Register::ModularDepthDisplay)?,
+        };
+        let non_modulated_level = self.read_register(Register::AntennaDriverNonModulatedLevelDefinition)?;
+        let regulated_voltage = match self.read_config::<RegulatedVoltage>()? {
+            RegulatedVoltage::External(voltage) => voltage,
+            RegulatedVoltage::Automatic => self.regulator_status()?.voltage.unwrap_or(RegulatorVoltage::V2_4),
+        };
+
+        Ok(CalibrationProfile {
+            trim,
+            am_modulated_level,
+            non_modulated_level,
+            regulated_voltage,
+        })
+    }
+
+    /// Restores a captured profile: forces the trim code, uses manual modulation depth
+    /// and defines the regulated voltage externally
+    pub fn apply_calibration_profile(
+        &mut self,
+        profile: &CalibrationProfile,
+    ) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.set_antenna_trim(AntennaTrim::Manual(profile.trim))?;
+        self.apply(ModulationDepthConfig::Manual)?;
+        self.set_antenna_driver_levels(
+            AmModulatedLevel { disabled_segments: profile.am_modulated_level },
+            NonModulatedLevel { disabled_segments: profile.non_modulated_level },
+        )?;
+        self.set_regulated_voltage(RegulatedVoltage::External(profile.regulated_voltage))
+    }
+
+    /// Runs the Check antenna resonance command to determine whether calibration is needed.
+    /// Fast enough to be used periodically.
+    pub fn check_antenna_resonance(&mut self) -> Result<AntennaResonance, Error<SPICS::SpiError, OPE>> {
+        self.execute_timed_command(Command::CheckAntennaResonance)?;
+
+        let raw = self.read_register(Register::ADConverterOutput)?;
+        Ok(AntennaResonance { raw })
+    }
+
+    /// Sets the target modulation depth and runs the Calibrate Modulation Depth command,
+    /// which adapts the AM modulated level to it. Oscillator and regulators have to be running.
+    pub fn calibrate_modulation_depth(
+        &mut self,
+        target: ModulationDepthConfig,
+    ) -> Result<ModulationDepthResult, Error<SPICS::SpiError, OPE>> {
+        self.apply(target)?;
+        self.execute_timed_command(Command::CalibrateModulationDepth)?;
+
+        let result = ModulationDepthResult {
+            disabled_segments: self.read_register(Register::ModularDepthDisplay)?,
+            carrier_level: self.read_register(Register::ADConverterOutput)?,
+        };
+        info!("Modulation depth calibration {:?}", result);
+        Ok(result)
+    }
+
+    /// Reads the Modulation Depth Display Register, the antenna driver segments
+    /// disabled during AM modulated state
+    pub fn modulation_depth_display(&mut self) -> Result<u8, Error<SPICS::SpiError, OPE>> {
+        self.read_register(Register::ModularDepthDisplay)
+    }
+
+    /// Runs the Measure RF command and returns the peak to peak amplitude on the RFI inputs.
+    /// Oscillator and regulators have to be running.
+    pub fn measure_rf_amplitude(&mut self) -> Result<Millivolts, Error<SPICS::SpiError, OPE>> {
+        self.execute_timed_command(Command::MeasureRF)?;
+
+        let raw = self.read_register(Register::ADConverterOutput)?;
+        Ok(Millivolts::from_rf_amplitude(raw))
+    }
+
+    /// Runs A/D conversion of the signal on the AD_IN pin.
+    /// Oscillator and regulators have to be running.
+    pub fn ad_convert(&mut self) -> Result<Millivolts, Error<SPICS::SpiError, OPE>> {
+        self.execute_timed_command(Command::ADConvert)?;
+
+        let raw = self.read_register(Register::ADConverterOutput)?;
+        Ok(Millivolts::from_ad_input(raw))
+    }
+
+    /// Switches to automatic regulated voltage definition and runs the Adjust regulators command,
+    /// which sets the regulated voltage 250mV below the measured VDD. Useful to re-adjust after supply changes.
+    pub fn adjust_regulators(&mut self) -> Result<RegulatorStatus, Error<SPICS::SpiError, OPE>> {
+        // Command is not accepted with external definition of the regulated voltage
+        self.set_regulated_voltage(RegulatedVoltage::Automatic)?;
+        self.execute_timed_command(Command::AdjustRegulators)?;
+
+        self.regulator_status()
+    }
+
+    /// Runs the checks of an end-of-line manufacturing test: register access, antenna resonance,
+    /// modulation depth, regulators and RF amplitude. Transmitter has to be enabled.
+    /// The configured modulation depth and regulated voltage are kept.
+    pub fn self_test(&mut self) -> Result<SelfTestReport, Error<SPICS::SpiError, OPE>> {
+        let registers_ok = self.check_scratch_register()?;
+        let resonance = self.check_antenna_resonance()?;
+        let modulation_depth = match self.read_config::<ModulationDepthConfig>()? {
+            target @ ModulationDepthConfig::Calibrated { .. } => Some(self.calibrate_modulation_depth(target)?),
+            ModulationDepthConfig::Manual => None,
+        };
+
+        let regulated_voltage = self.read_config::<RegulatedVoltage>()?;
+        let regulators = self.adjust_regulators()?;
+        self.set_regulated_voltage(regulated_voltage)?;
+
+        let rf_amplitude = self.measure_rf_amplitude()?;
+
+        let report = SelfTestReport {
+            resonance,
+            modulation_depth,
+            regulators,
+            rf_amplitude,
+            registers_ok,
+        };
+        info!("Self test {:?}", report);
+        Ok(report)
+    }
+
+    /// Writes test patterns to the Number of Transmitted Bytes Register 1 and reads them back,
+    /// the original value is restored afterwards
+    fn check_scratch_register(&mut self) -> Result<bool, Error<SPICS::SpiError, OPE>> {
+        let reg = Register::NumberOfTransmittedBytes1;
+        let original = self.read_register(reg)?;
+        let mut ok = true;
+        for pattern in [0x55, 0xAA] {
+            match self.write_register(reg, pattern) {
+                Ok(()) => ok &= self.read_register(reg)? == pattern,
+                Err(Error::VerifyFailed { .. }) => ok = false,
+                Err(e) => return Err(e),
+            }
+        }
+        self.write_register(reg, original)?;
+        Ok(ok)
+    }
+
+    /// Estimates the VDD supply under full load. Adjust regulators sets the regulated voltage 250mV below
+    /// the measured VDD, so the estimate saturates at 2.65V and 3.65V. The regulated voltage definition is kept.
+    pub fn measure_supply(&mut self) -> Result<Millivolts, Error<SPICS::SpiError, OPE>> {
+        let regulated_voltage = self.read_config::<RegulatedVoltage>()?;
+        let status = self.adjust_regulators()?;
+        if regulated_voltage != RegulatedVoltage::Automatic {
+            self.set_regulated_voltage(regulated_voltage)?;
+        }
+
+        let regulated = status.voltage.map(|voltage| voltage.millivolts()).unwrap_or(0);
+        Ok(Millivolts(regulated + 250))
+    }
+
+    /// Sets the minimum supply checked before every transceive, see `Config::min_supply_mv`
+    pub fn set_min_supply(&mut self, min_supply_mv: u16) {
+        self.config.min_supply_mv = min_supply_mv;
+    }
+
+    /// Reads the actual regulated voltage from the Regulators Display Register
+    pub fn regulator_status(&mut self) -> Result<RegulatorStatus, Error<SPICS::SpiError, OPE>> {
+        Ok(RegulatorStatus::from(self.read_register(Register::RegulatorsDisplay)?))
+    }
+
+    /// Reads the RSSI and gain reduction of the receiver, useful to diagnose reception at range
+    pub fn receiver_state(&mut self) -> Result<ReceiverState, Error<SPICS::SpiError, OPE>> {
+        Ok(ReceiverState::from(self.read_register(Register::ReceiverStateDisplay)?))
+    }
+
+    /// Clears the peak hold RSSI value and restarts the measurement
+    pub fn clear_rssi(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.execute_command(Command::ClearRSSI)
+    }
+
+    /// Defines the external field thresholds used for target activation and RF collision avoidance,
+    /// has to be written before NFCIP-1 operation
+    pub fn set_field_detection_threshold(
+        &mut self,
+        threshold: FieldDetectionThreshold,
+    ) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.apply(threshold)
+    }
+
+    /// Enables (or disables with `None`) closed-loop output power adjustment, see `Config::adaptive_power`
+    pub fn set_adaptive_power(&mut self, adaptive_power: Option<AdaptivePower>) {
+        self.config.adaptive_power = adaptive_power;
+    }
+
+    fn adapt_output_power(&mut self, adaptive_power: AdaptivePower) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        let rssi = self.receiver_state()?.rssi;
+        let level = self.read_config::<NonModulatedLevel>()?;
+        let disabled_segments = adaptive_power.next_level(level.disabled_segments, rssi);
+        if disabled_segments != level.disabled_segments {
+            debug!("RSSI {}, non-modulated level {:x} -> {:x}", rssi, level.disabled_segments, disabled_segments);
+            self.apply(NonModulatedLevel { disabled_segments })?;
+        }
+        Ok(())
+    }
+
+    /// Sets the antenna driver segments disabled in AM modulated and in non-modulated state,
+    /// to match the antenna matching network
+    pub fn set_antenna_driver_levels(
+        &mut self,
+        am_modulated: AmModulatedLevel,
+        non_modulated: NonModulatedLevel,
+    ) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.write_registers(
+            Register::AntennaDriverAMModulatedLevelDefinition,
+            &[am_modulated.bits(), non_modulated.bits()],
+        )
+    }
+
+    /// Runs the Squelch command which reduces receiver gain until noise no longer
+    /// produces transitions on the receiver output. Transmitter and receiver have to be operating.
+    pub fn squelch(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.execute_timed_command(Command::Squelch)?;
+        Ok(())
+    }
+
+    /// Resumes the gain settings which were in place before Squelch command
+    pub fn clear_squelch(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.execute_command(Command::ClearSquelch)
+    }
+
+    /// Ignores all received modulation until `unmask_receive` is called
+    pub fn mask_receive(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.execute_command(Command::MaskReceiveData)
+    }
+
+    /// Enables processing of received data again. Sent right after the end of transmission
+    /// it also skips the 40µs the receiver is normally kept disabled
+    pub fn unmask_receive(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.execute_command(Command::UnmaskReceiveData)
+    }
+
+    /// Sets the window receive data is masked after every transmission, see `Config::receive_mask_window_us`
+    pub fn set_receive_mask_window(&mut self, window_us: u16) {
+        self.config.receive_mask_window_us = window_us;
+    }
+
+    /// Enables or disables squelch after every receive, see `Config::auto_squelch`
+    pub fn set_auto_squelch(&mut self, enable: bool) {
+        self.config.auto_squelch = enable;
+    }
+
+    /// Selects the protocol mode and data rates. The receiver filter is preset
+    /// by the chip for the new mode, so the persisted receiver configuration follows it.
+    pub fn set_mode(&mut self, mode: OperatingMode) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.apply(mode)?;
+        self.mode = mode;
+        self.receiver_config.filter = mode.receiver_filter();
+        Ok(())
+    }
+
+    pub fn mode(&self) -> OperatingMode {
+        self.mode
+    }
+
+    /// Switches the ISO-14443A or B mode to `tx` from reader to PICC and `rx` from PICC to reader,
+    /// the receiver filter follows the receive rate. NFCIP-1 only runs at 106 kbps on the AS3910,
+    /// other rates fail with `Error::InvalidArgument` in that mode.
+    pub fn set_bit_rate(&mut self, tx: BitRate, rx: BitRate) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        let mode = match self.mode {
+            OperatingMode::Iso14443a { .. } => OperatingMode::Iso14443a { tx, rx },
+            OperatingMode::Iso14443b { .. } => OperatingMode::Iso14443b { tx, rx },
+            OperatingMode::Nfcip1Active if tx == BitRate::Kbps106 && rx == BitRate::Kbps106 => {
+                OperatingMode::Nfcip1Active
+            }
+            OperatingMode::Nfcip1Active => return Err(Error::InvalidArgument),
+        };
+        self.set_mode(mode)
+    }
+
+    /// Sets receiver AGC, gain reduction and filter configuration.
+    /// The configuration persists and is rewritten before every transceive.
+    pub fn set_receiver_config(&mut self, config: ReceiverConfig) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.receiver_config = config;
+        self.apply(config)
+    }
+
+    pub fn receiver_config(&self) -> ReceiverConfig {
+        self.receiver_config
+    }
+
+    /// Sets ISO-14443A framing configuration (parity checking, modulation pulse length).
+    /// Receive CRC handling is still selected per transceive.
+    pub fn set_iso14443a_config(&mut self, config: Iso14443aConfig) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.iso14443a_config = config;
+        self.apply(config)
+    }
+
+    pub fn iso14443a_config(&self) -> Iso14443aConfig {
+        self.iso14443a_config
+    }
+
+    /// Selects AM or PM demodulation
+    pub fn set_demodulation(&mut self, demodulation: Demodulation) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.modify_register(Register::ConfigurationRegister5, |val| {
+            let mut flags = Configuration5Flags::from_bits_truncate(val);
+            flags.set(Configuration5Flags::PMD, demodulation == Demodulation::Pm);
+            flags.bits()
+        })
+    }
+
+    /// Enables the RF output and waits `Config::field_guard_ms` for the field to settle, so that
+    /// a PICC entering the field has time to power up before the first command
+    pub fn field_on(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.modify_register(Register::OperationControl, |val| {
+            (OperationControlFlags::from_bits_truncate(val) | OperationControlFlags::TX_EN).bits()
+        })?;
+        self.delay.delay_ms(self.config.field_guard_ms);
+        Ok(())
+    }
+
+    /// Disables the RF output, oscillator and receiver stay enabled
+    pub fn field_off(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        // Without field every PICC is reset, an activated one has to go through RATS again
+        self.iso_dep = None;
+        self.modify_register(Register::OperationControl, |val| {
+            (OperationControlFlags::from_bits_truncate(val) - OperationControlFlags::TX_EN).bits()
+        })
+    }
+
+    /// Runs `f` with the RF field on and switches it off afterwards, also when `f` failed
+    pub fn with_field<T, F>(&mut self, f: F) -> Result<T, Error<SPICS::SpiError, OPE>>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Error<SPICS::SpiError, OPE>>,
+    {
+        self.field_on()?;
+        let result = f(self);
+        self.field_off()?;
+        result
+    }
+
+    /// Sets the FIFO fill levels at which the water level interrupt is raised during streaming
+    pub fn set_fifo_water_level(&mut self, level: FifoWaterLevel) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.modify_register(Register::ConfigurationRegister5, |val| level.update_bits(val))
+    }
+
+    pub fn fifo_water_level(&mut self) -> Result<FifoWaterLevel, Error<SPICS::SpiError, OPE>> {
+        Ok(FifoWaterLevel::from_bits(self.read_register(Register::ConfigurationRegister5)?))
+    }
+
+    /// Reads the register, applies `f` to its value and writes the result back.
+    /// Allows toggling individual bits without clobbering the rest of the register.
+    pub fn modify_register<F>(&mut self, reg: Register, f: F) -> Result<(), Error<SPICS::SpiError, OPE>>
+    where
+        F: FnOnce(u8) -> u8,
+    {
+        let val = self.read_register(reg)?;
+        self.write_register(reg, f(val))
+    }
+
+    pub fn read_register(&mut self, reg: Register) -> Result<u8, Error<SPICS::SpiError, OPE>> {
+        let mut buffer = [reg.read_address(), 0];
+
+        self.spi_with_custom_cs.with_cs_high(&mut self.cs,|spi| {
+            // Transfers in place, the value is shifted into the second byte
+            spi.transfer(&mut buffer)?;
+
+            Ok(())
+        }).map_err(Error::SpiWithCS)?;
+        let value = buffer[1];
+        debug!("Read register {:?} got value value: 0b{:08b}", reg, value);
+        self.trace(SpiEvent::ReadRegister { reg, value });
+        Ok(value)
+    }
+
+    /// Reads consecutive registers starting at `start` in a single SPI transaction
+    /// using address auto-increment.
+    pub fn read_registers(&mut self, start: Register, buffer: &mut [u8]) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        self.spi_with_custom_cs.with_cs_high(&mut self.cs, |spi| {
+            spi.transfer(&mut [start.read_address()])?;
+            spi.transfer(buffer)?;
+
+            debug!("Read registers from {:?}: {:x?}", start, buffer);
+            Ok(())
+        }).map_err(Error::SpiWithCS)?;
+        self.trace(SpiEvent::ReadRegisters { start, values: buffer });
+        Ok(())
+    }
+
+    /// Reads the contiguous block of registers `from..=to` in a single burst.
+    /// Keep in mind that including the Interrupt register clears pending interrupts.
+    pub fn read_register_range(
+        &mut self,
+        from: Register,
+        to: Register,
+    ) -> Result<impl Iterator<Item = (Register, u8)>, Error<SPICS::SpiError, OPE>> {
+        let mut buffer = [0u8; REGISTER_COUNT];
+        let len = (to as usize + 1).saturating_sub(from as usize);
+        if len > 0 {
+            self.read_registers(from, buffer.get_mut(..len).ok_or(Error::NoRoom)?)?;
+        }
+
+        Ok((from as u8..)
+            .zip(buffer.into_iter().take(len))
+            .filter_map(|(address, val)| Register::try_from(address).ok().map(|reg| (reg, val))))
+    }
+
+    /// Writes consecutive registers starting at `start` in a single SPI transaction
+    /// using address auto-increment.
+    pub fn write_registers(&mut self, start: Register, values: &[u8]) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        debug!("Write registers from {:?}: {:x?}", start, values);
+        self.spi_with_custom_cs.with_cs_high(&mut self.cs, |spi| {
+            spi.write(&[start.write_address()])?;
+            spi.write(values)?;
+
+            Ok(())
+        }).map_err(Error::SpiWithCS)?;
+        self.trace(SpiEvent::WriteRegisters { start, values });
+        if self.config.cache_registers {
+            for (slot, val) in self.shadow.iter_mut().skip(start as usize).zip(values) {
+                *slot = Some(*val);
+            }
+        }
+        if self.config.verify_writes {
+            for (address, wrote) in (start as u8..).zip(values) {
+                if let Ok(reg) = Register::try_from(address) {
+                    self.verify_register(reg, *wrote)?;
+                }
+            }
+        }
+        Ok(())
     }
 
     fn read_fifo<'b>(&mut self, buffer: &'b mut [u8]) -> Result<&'b [u8], Error<SPICS::SpiError, OPE>> {
-        self.spi_with_custom_cs.with_cs_high(&mut self.cs, move |spi| {
+        let data = self.spi_with_custom_cs.with_cs_high(&mut self.cs, move |spi| {
             // initiate fifo read
-            spi.transfer(&mut [0b10111111])?;
+            spi.write(&[0b10111111])?;
 
-            let n = buffer.len();
-            for slot in &mut buffer[..n] {
-                *slot = spi.transfer(&mut [0])?[0];
-            }
+            // clock out the whole buffer in one transfer
+            buffer.fill(0);
+            let data = spi.transfer(buffer)?;
 
-            debug!("Read from fifo: {:x?}", buffer);
-            Ok(&*buffer)
-        }).map_err(Error::SpiWithCS)
+            debug!("Read from fifo: {:x?}", data);
+            Ok(data)
+        }).map_err(Error::SpiWithCS)?;
+        self.trace(SpiEvent::ReadFifo(data));
+        Ok(data)
     }
 
     fn write_fifo(&mut self, bytes: &[u8]) -> Result<(), Error<SPICS::SpiError, OPE>> {
         debug!("Write in fifo: {:x?}", bytes);
-        self.spi_with_custom_cs.with_cs_high(&mut self.cs,|spi| {
-            // initiate fifo write
-            spi.transfer(&mut [0b10000000])?;
+        if bytes.len() > FIFO_SIZE {
+            return Err(Error::NoRoom);
+        }
+        // opcode and payload in one contiguous buffer, so DMA capable HALs can send it at once
+        let mut frame = [0u8; FIFO_SIZE + 1];
+        // initiate fifo write
+        frame[0] = 0b10000000;
+        frame.get_mut(1..=bytes.len()).ok_or(Error::NoRoom)?.copy_from_slice(bytes);
+        let frame = frame.get(..=bytes.len()).ok_or(Error::NoRoom)?;
+        let chunk_size = match self.config.spi_chunk_size {
+            0 => frame.len(),
+            size => size,
+        };
 
-            spi.write(bytes)?;
+        self.spi_with_custom_cs.with_cs_high(&mut self.cs,|spi| {
+            for chunk in frame.chunks(chunk_size) {
+                spi.write(chunk)?;
+            }
 
             Ok(())
-        }).map_err(Error::SpiWithCS)
+        }).map_err(Error::SpiWithCS)?;
+        self.trace(SpiEvent::WriteFifo(bytes));
+        Ok(())
     }
 
-    fn wait_for_interrupt(&mut self, timeout_in_ms: u16) -> Result<InterruptFlags, Error<SPICS::SpiError, OPE>> {
+    /// Polls the interrupt line for up to `timeout_in_ms` and returns (and clears) all flags of
+    /// the Interrupt Register, including sources that are masked. Use `InterruptFlags::events`
+    /// to react to each of them. The flags are also added to the latch returned by `take_interrupts`.
+    pub fn wait_for_interrupt(&mut self, timeout_in_ms: u16) -> Result<InterruptFlags, Error<SPICS::SpiError, OPE>> {
         debug!("Wait for interrupt {}ms", timeout_in_ms);
+        let mut flags = InterruptFlags::empty();
+        // Only consecutive empty reads within one wait count towards a stuck line
+        self.empty_interrupt_reads = 0;
         let mut i = 0;
         loop {
-            if self.intr.is_high().map_err(Error::InterruptPin)? {
-                return Ok(InterruptFlags::from_bits_truncate(
-                    self.read_register(Register::Interrupt)?,
-                ));
+            if self.poll_interrupt(&mut flags)? {
+                debug!("Got interrupt {}", flags);
+                return Ok(flags);
+            }
+
+            if i >= timeout_in_ms {
+                break;
+            }
+            self.delay.delay_ms(1);
+            i += 1;
+        }
+
+        Err(Error::InterruptTimeout)
+    }
+
+    /// Checks for a pending unmasked interrupt according to `Config::interrupt_line`, or the next
+    /// event of the `set_event_source` source. Flags read from the Interrupt Register are added
+    /// to `flags`, also when only masked sources are set, since the read cleared them.
+    fn poll_interrupt(&mut self, flags: &mut InterruptFlags) -> Result<bool, Error<SPICS::SpiError, OPE>> {
+        if let Some(source) = self.event_source {
+            let read = match source() {
+                None => return Ok(false),
+                Some(irq::IrqEvent::Pending) => self.read_interrupts()?,
+                // Already read by the ISR, latched like a read of the driver
+                Some(irq::IrqEvent::Flags(read)) => {
+                    self.latched |= read;
+                    read
+                }
+            };
+            *flags |= read;
+            return Ok(!read.is_empty());
+        }
+        let asserted = match self.config.interrupt_line {
+            InterruptLine::ActiveHigh => self.intr.is_high().map_err(Error::InterruptPin)?,
+            InterruptLine::ActiveLow => self.intr.is_low().map_err(Error::InterruptPin)?,
+            InterruptLine::Polled => {
+                let read = self.read_interrupts()?;
+                *flags |= read;
+                let mask = match self.cached(Register::MaskInterrupt) {
+                    Some(mask) => mask,
+                    None => self.read_register(Register::MaskInterrupt)?,
+                };
+                let masked = InterruptFlags::from_bits_truncate(mask);
+                return Ok(read.intersects(!masked));
+            }
+        };
+        if !asserted {
+            self.empty_interrupt_reads = 0;
+            return Ok(false);
+        }
+        let read = self.read_interrupts()?;
+        *flags |= read;
+        // Reading the register releases the line, so a line that stays asserted without
+        // any flag set is shorted or driven by something else
+        if read.is_empty() {
+            self.empty_interrupt_reads += 1;
+            if self.empty_interrupt_reads >= IRQ_STUCK_READS {
+                self.empty_interrupt_reads = 0;
+                return Err(Error::IrqLineStuck);
+            }
+            return Ok(false);
+        }
+        self.empty_interrupt_reads = 0;
+        Ok(true)
+    }
+
+    /// Sets how pending interrupts are detected, see `Config::interrupt_line`
+    pub fn set_interrupt_line(&mut self, line: InterruptLine) {
+        self.config.interrupt_line = line;
+    }
+
+    /// Polls the interrupt pin for up to `timeout_in_ms` until `flags` fired according to `mode`.
+    /// Returns every flag read from the Interrupt Register in the meantime, which may include
+    /// flags outside of `flags`.
+    ///
+    /// The pin only signals unmasked sources, so `flags` should be enabled with `subscribe`.
+    pub fn wait_for_flags(
+        &mut self,
+        flags: InterruptFlags,
+        mode: WaitMode,
+        timeout_in_ms: u16,
+    ) -> Result<InterruptFlags, Error<SPICS::SpiError, OPE>> {
+        debug!("Wait for {:?} of {} {}ms", mode, flags, timeout_in_ms);
+        let mut fired = InterruptFlags::empty();
+        // Only consecutive empty reads within one wait count towards a stuck line
+        self.empty_interrupt_reads = 0;
+        let mut i = 0;
+        loop {
+            if self.poll_interrupt(&mut fired)? && mode.satisfied(fired, flags) {
+                debug!("Got interrupts {}", fired);
+                return Ok(fired);
             }
 
             if i >= timeout_in_ms {
@@ -498,6 +2655,67 @@ where
         }).map_err(Error::SpiWithCS)
     }
 
+    fn trace(&self, event: SpiEvent) {
+        if let Some(hook) = self.trace_hook {
+            hook(&event);
+        }
+    }
+
+}
+
+/// Interrupt sources enabled by `AS3910::subscribe`, they are disabled again on drop
+pub struct InterruptSubscription<'a, SPICS, CS, INTR, DELAY>
+where
+    SPICS: SpiWithCustomCS,
+    CS: OutputPin,
+    INTR: InputPin<Error = CS::Error>,
+    DELAY: delay::DelayMs<u16> + delay::DelayUs<u16>,
+{
+    as3910: &'a mut AS3910<SPICS, CS, INTR, DELAY>,
+    /// Sources that were not enabled before the subscription
+    added: InterruptFlags,
+}
+
+impl<SPICS, CS, INTR, DELAY> core::ops::Deref for InterruptSubscription<'_, SPICS, CS, INTR, DELAY>
+where
+    SPICS: SpiWithCustomCS,
+    CS: OutputPin,
+    INTR: InputPin<Error = CS::Error>,
+    DELAY: delay::DelayMs<u16> + delay::DelayUs<u16>,
+{
+    type Target = AS3910<SPICS, CS, INTR, DELAY>;
+
+    fn deref(&self) -> &Self::Target {
+        self.as3910
+    }
+}
+
+impl<SPICS, CS, INTR, DELAY> core::ops::DerefMut for InterruptSubscription<'_, SPICS, CS, INTR, DELAY>
+where
+    SPICS: SpiWithCustomCS,
+    CS: OutputPin,
+    INTR: InputPin<Error = CS::Error>,
+    DELAY: delay::DelayMs<u16> + delay::DelayUs<u16>,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as3910
+    }
+}
+
+impl<SPICS, CS, INTR, DELAY> Drop for InterruptSubscription<'_, SPICS, CS, INTR, DELAY>
+where
+    SPICS: SpiWithCustomCS,
+    CS: OutputPin,
+    INTR: InputPin<Error = CS::Error>,
+    DELAY: delay::DelayMs<u16> + delay::DelayUs<u16>,
+{
+    fn drop(&mut self) {
+        self.as3910.persistent_interrupts -= self.added;
+        // Errors can't be reported from drop, a failed restore shows up on the next transfer
+        if self.as3910.write_interrupt_mask(InterruptFlags::empty()).is_err() {
+            error!("Failed to restore interrupt mask");
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -506,13 +2724,202 @@ pub enum Error<E, OPE> {
     InterruptPin(OPE),
 
     /// Set when Calibrate antenna sequence was not able to adjust resonance
-    AntennaCalibration,
+    AntennaCalibration(AntennaCalibrationResult),
 
+    /// An interrupt expected from the chip itself didn't arrive (oscillator, end of transmission)
     InterruptTimeout,
+    /// The PICC didn't answer within the timeout after the frame was sent
+    Timeout(DebugSnapshot),
+    /// No PICC answered REQA or WUPA, nothing is in the field
+    NoCard,
     NoRoom,
-    Collision,
+    /// BCC received during anticollision doesn't match the UID bytes
+    Bcc,
+    /// Registers don't hold their reset values after Set Default, see `AS3910::probe`
+    ChipNotFound,
+    /// Interrupt line stays asserted while no interrupt is pending, it is shorted or shared
+    IrqLineStuck,
+    /// Bit collision in the response, several PICCs answered
+    Collision(CollisionPosition, DebugSnapshot),
     Proprietary,
-    AntiCollisionMaxLoopsReached,
+    /// PICC response is inconsistent with the protocol, e.g. more than three cascade levels
+    InvalidResponse,
+    /// `select` sent this many anticollision frames in one cascade level without resolving the UID
+    AntiCollisionMaxLoopsReached(u8),
     IncompleteFrame,
     NotAcknowledged,
+    /// Register read back after write didn't match the written value
+    VerifyFailed { reg: Register, wrote: u8, read: u8 },
+    /// Response couldn't be decoded (framing, parity or subcarrier coding error).
+    /// Whatever was received is still in the receive buffer
+    Framing(Received, DebugSnapshot),
+    /// CRC of the response didn't match. The corrupt data is still in the receive buffer
+    Crc(Received, DebugSnapshot),
+    /// FIFO overflowed, received data was lost
+    FifoOverflow,
+    /// Frame to transmit is longer than the 1023 bytes the chip can count
+    FrameTooLong,
+    /// Supply voltage is below `Config::min_supply_mv`, RF operation is not reliable
+    SupplyLow(Millivolts),
+    /// A parameter is outside of the range the protocol defines
+    InvalidArgument,
+    /// No PICC was activated with `AS3910::rats`
+    NotActivated,
+    /// PICC answered an APDU with this status word instead of `apdu::SW_SUCCESS`
+    Status(u16),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(bytes: u8, bits: u8) -> CollisionPosition {
+        CollisionPosition {
+            bytes,
+            bits,
+            progress: true,
+        }
+    }
+
+    fn received(bytes: usize, last_byte_bits: u8) -> Received {
+        Received {
+            bytes,
+            last_byte_bits,
+            interrupts: InterruptFlags::empty(),
+        }
+    }
+
+    #[test]
+    fn received_bits() {
+        assert_eq!(received(0, 8).bits(), 0);
+        assert_eq!(received(0, 4).bits(), 0);
+        assert_eq!(received(1, 8).bits(), 8);
+        assert_eq!(received(5, 8).bits(), 40);
+        // 4 bit response frame
+        assert_eq!(received(1, 4).bits(), 4);
+        assert_eq!(received(3, 4).bits(), 20);
+    }
+
+    #[test]
+    fn collision_position_from_register() {
+        assert_eq!(CollisionPosition::from_register(0x00), position(0, 0));
+        assert_eq!(CollisionPosition::from_register(0x2E), position(2, 7));
+        assert_eq!(CollisionPosition::from_register(0x64), position(6, 2));
+        // Bit 0 is not part of the position
+        assert_eq!(CollisionPosition::from_register(0xF1), position(15, 0));
+    }
+
+    #[test]
+    fn uid_bit_table() {
+        let table = [
+            (2, 0, 0),
+            (2, 1, 1),
+            (2, 7, 7),
+            (3, 0, 8),
+            (3, 5, 13),
+            (4, 0, 16),
+            (4, 7, 23),
+            (5, 0, 24),
+            (5, 6, 30),
+            (6, 0, 32),
+            (6, 7, 39),
+        ];
+        for (bytes, bits, uid_bit) in table {
+            assert_eq!(position(bytes, bits).uid_bit(0), Some(uid_bit), "{} {}", bytes, bits);
+        }
+    }
+
+    #[test]
+    fn uid_bit_every_position() {
+        let mut seen = [false; 40];
+        for bytes in 0..16 {
+            for bits in 0..8 {
+                let expected = if (2..7).contains(&bytes) {
+                    Some((bytes - 2) * 8 + bits)
+                } else {
+                    None
+                };
+                assert_eq!(position(bytes, bits).uid_bit(0), expected, "{} {}", bytes, bits);
+                if let Some(seen) = expected.and_then(|bit| seen.get_mut(bit as usize)) {
+                    assert!(!*seen);
+                    *seen = true;
+                }
+            }
+        }
+        // Every UID bit 0..40 is reached by exactly one position
+        assert!(seen.iter().all(|seen| *seen));
+    }
+
+    #[test]
+    fn uid_bit_outside_of_uid() {
+        // Collision in the SEL or NVB byte
+        assert_eq!(position(0, 0).uid_bit(0), None);
+        assert_eq!(position(1, 7).uid_bit(0), None);
+        // Collision after the BCC
+        assert_eq!(position(7, 0).uid_bit(0), None);
+        assert_eq!(position(u8::MAX, 7).uid_bit(0), None);
+        // Large positions don't overflow
+        assert_eq!(position(u8::MAX, u8::MAX).uid_bit(0), None);
+    }
+
+    #[test]
+    fn uid_bit_known_bits() {
+        // Collision after the known bits makes progress
+        assert_eq!(position(3, 4).uid_bit(0), Some(12));
+        assert_eq!(position(3, 4).uid_bit(11), Some(12));
+        // Collision on the first unknown bit
+        assert_eq!(position(3, 4).uid_bit(12), Some(12));
+        // Collision within the bits already sent
+        assert_eq!(position(3, 4).uid_bit(13), None);
+        assert_eq!(position(2, 0).uid_bit(1), None);
+        assert_eq!(position(6, 7).uid_bit(40), None);
+        assert_eq!(position(6, 7).uid_bit(u8::MAX), None);
+    }
+
+    #[test]
+    fn without_progress() {
+        let position = position(3, 4).without_progress();
+        assert!(!position.progress);
+        assert_eq!((position.bytes, position.bits), (3, 4));
+    }
+
+    #[test]
+    fn set_uid_bit_every_position() {
+        for bit in 0..40 {
+            let mut tx = [0u8; 9];
+            assert_eq!(set_uid_bit(&mut tx, bit), Some(()));
+            let mut expected = [0u8; 9];
+            if let Some(byte) = expected.get_mut(2 + bit as usize / 8) {
+                *byte = 1 << (bit % 8);
+            }
+            assert_eq!(tx, expected, "{}", bit);
+        }
+    }
+
+    #[test]
+    fn set_uid_bit_keeps_known_bits() {
+        let mut tx = [0x93, 0x20, 0x0F, 0, 0, 0, 0, 0, 0];
+        assert_eq!(set_uid_bit(&mut tx, 4), Some(()));
+        assert_eq!(tx, [0x93, 0x20, 0x1F, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn set_uid_bit_outside_of_frame() {
+        let mut tx = [0u8; 9];
+        // The select frame holds the SEL and NVB bytes and 56 UID bits
+        assert_eq!(set_uid_bit(&mut tx, 55), Some(()));
+        assert_eq!(set_uid_bit(&mut tx, 56), None);
+        assert_eq!(set_uid_bit(&mut tx, u8::MAX), None);
+    }
+
+    #[test]
+    fn card_type_reports_ambiguous_picc() {
+        let desfire_atqa = AtqA { bytes: [0x44, 0x03] };
+        let atqa = AtqA { bytes: [0x04, 0x00] };
+        assert_eq!(CardType::from(desfire_atqa, Sak::from(0x20)), CardType::MifareDesfireOrPlus);
+        assert_eq!(CardType::from(atqa, Sak::from(0x20)), CardType::Iso14443_4);
+        assert_eq!(CardType::from(atqa, Sak::from(0x08)), CardType::Mifare1k);
+        assert_eq!(CardType::from(atqa, Sak::from(0x28)), CardType::SmartMx1k);
+        assert_eq!(CardType::from(atqa, Sak::from(0x38)), CardType::SmartMx4k);
+    }
 }
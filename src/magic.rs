@@ -0,0 +1,123 @@
+//! Helpers for UID changeable MIFARE Classic compatible "magic" cards.
+//!
+//! Gen1a cards open a backdoor after HLTA followed by the 7 bit frame 0x40 and the byte 0x43,
+//! afterwards every block including the manufacturer block 0 is readable and writable
+//! without authentication.
+//!
+//! Gen2 (CUID) block 0 write helpers are not part of this module. These cards accept a regular
+//! write to block 0, but only after a MIFARE Classic authentication. Its Crypto1 encrypted
+//! exchange needs a parity bit per byte encrypted with the keystream, while the AS3910 always
+//! generates the parity of the transmitted byte itself (Configuration Register 3 only controls
+//! receive parity, see `TransceiveOptions::parity_check`). Gen2 support needs a reader which
+//! can transmit caller supplied parity bits.
+
+use embedded_hal::blocking::delay;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+use crate::{crc, picc, Error, SpiWithCustomCS, TransceiveOptions, AS3910};
+
+/// First backdoor command, sent as a 7 bit short frame
+pub const GEN1A_UNLOCK_1: u8 = 0x40;
+
+/// Second backdoor command, sent as a full byte without CRC
+pub const GEN1A_UNLOCK_2: u8 = 0x43;
+
+/// 4 bit acknowledge of MIFARE Classic commands
+const ACK: u8 = 0x0A;
+
+/// Length of a MIFARE Classic block
+pub const BLOCK_LEN: usize = 16;
+
+impl<OPE, CS, INTR, SPICS, DELAY> AS3910<SPICS, CS, INTR, DELAY>
+where
+    SPICS: SpiWithCustomCS,
+    CS: OutputPin<Error = OPE>,
+    INTR: InputPin<Error = OPE>,
+    DELAY: delay::DelayMs<u16> + delay::DelayUs<u16>,
+{
+    /// Halts the selected gen1a card and opens its backdoor. Fails with
+    /// `Error::NotAcknowledged` when the card isn't a gen1a magic card
+    pub fn gen1a_unlock(&mut self) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        info!("Gen1a unlock");
+        self.hlta()?;
+        let options = self.ack_options();
+        let mut rx = [0u8; 1];
+        let received = self.send_short_frame(GEN1A_UNLOCK_1, &mut rx, options)?;
+        check_ack(received, rx)?;
+        let received = self.transceive(&[GEN1A_UNLOCK_2], &mut rx, options)?;
+        check_ack(received.bits(), rx)
+    }
+
+    /// Writes the manufacturer block 0 of a gen1a card through the backdoor, changing its UID.
+    /// The card has to be selected. Fails with `Error::Bcc` when byte 4 isn't the BCC of the
+    /// 4 byte UID in front of it, a card with a wrong BCC no longer answers anticollision.
+    pub fn gen1a_write_block0(&mut self, block: &[u8; BLOCK_LEN]) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        let (uid, rest) = block.split_first_chunk::<4>().ok_or(Error::InvalidArgument)?;
+        if rest.first() != Some(&uid.iter().fold(0, |bcc, byte| bcc ^ byte)) {
+            return Err(Error::Bcc);
+        }
+        self.gen1a_unlock()?;
+        self.mifare_write(0, block)
+    }
+
+    /// Sends a MIFARE Classic WRITE of `block` to block number `address`, both of its parts
+    /// have to be acknowledged. The 4 bit ACK carries no CRC, so the CRC is appended in software
+    fn mifare_write(&mut self, address: u8, block: &[u8; BLOCK_LEN]) -> Result<(), Error<SPICS::SpiError, OPE>> {
+        info!("MIFARE write block {}", address);
+        let options = self.ack_options();
+        let mut frame = [0u8; BLOCK_LEN + 2];
+        let mut rx = [0u8; 1];
+
+        frame[0] = picc::Command::MfWrite as u8;
+        frame[1] = address;
+        let len = crc::append_crc_a(&mut frame, 2).ok_or(Error::NoRoom)?;
+        let received = self.transceive(frame.get(..len).ok_or(Error::NoRoom)?, &mut rx, options)?;
+        check_ack(received.bits(), rx)?;
+
+        frame.get_mut(..BLOCK_LEN).ok_or(Error::NoRoom)?.copy_from_slice(block);
+        let len = crc::append_crc_a(&mut frame, BLOCK_LEN).ok_or(Error::NoRoom)?;
+        let received = self.transceive(frame.get(..len).ok_or(Error::NoRoom)?, &mut rx, options)?;
+        check_ack(received.bits(), rx)
+    }
+
+    /// Options of a command answered with the 4 bit ACK/NAK, which has neither parity nor CRC
+    fn ack_options(&self) -> TransceiveOptions {
+        self.transceive_options()
+            .crc(false)
+            .parity_check(false)
+            .four_bit_response(true)
+    }
+}
+
+/// Checks that a response of `bits` bits is the 4 bit ACK, which the chip puts in the
+/// high nibble of the received byte
+fn check_ack<E, OPE>(bits: usize, rx: [u8; 1]) -> Result<(), Error<E, OPE>> {
+    match rx {
+        [byte] if bits == 4 && byte >> 4 == ACK => Ok(()),
+        _ => Err(Error::NotAcknowledged),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Result = core::result::Result<(), Error<(), ()>>;
+
+    #[test]
+    fn check_ack_accepts_4_bit_ack() {
+        assert!(matches!(check_ack(4, [0xA0]) as Result, Ok(())));
+        // Bits below the 4 bit frame are ignored
+        assert!(matches!(check_ack(4, [0xAF]) as Result, Ok(())));
+    }
+
+    #[test]
+    fn check_ack_rejects_nak_and_other_frames() {
+        assert!(matches!(check_ack(4, [0x40]) as Result, Err(Error::NotAcknowledged)));
+        assert!(matches!(check_ack(4, [0x50]) as Result, Err(Error::NotAcknowledged)));
+        // ACK in the wrong nibble
+        assert!(matches!(check_ack(4, [0x0A]) as Result, Err(Error::NotAcknowledged)));
+        assert!(matches!(check_ack(0, [0xA0]) as Result, Err(Error::NotAcknowledged)));
+        assert!(matches!(check_ack(8, [0xA0]) as Result, Err(Error::NotAcknowledged)));
+    }
+}
@@ -0,0 +1,77 @@
+//! Results of the AS3910 A/D converter based measurements.
+
+use crate::config::RegulatorVoltage;
+use crate::register::{ReceiverStateDisplayFlags, RegulatorsDisplayFlags};
+
+/// Voltage in millivolts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Millivolts(pub u16);
+
+impl Millivolts {
+    /// Converts a Measure RF result, one LSB represents 13.02mVpp on the RFI inputs
+    pub fn from_rf_amplitude(raw: u8) -> Self {
+        Millivolts((raw as u32 * 1302 / 100) as u16)
+    }
+
+    /// Converts an AD convert result in absolute mode, 0 to 2V range with 7.8125mV LSB
+    pub fn from_ad_input(raw: u8) -> Self {
+        Millivolts((raw as u32 * 78125 / 10000) as u16)
+    }
+}
+
+/// Decoded Regulators Display Register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegulatorStatus {
+    /// Actual regulated voltage, `None` in Power-down mode when regulators are not operating
+    pub voltage: Option<RegulatorVoltage>,
+    /// Raw Regulators Display Register value
+    pub raw: u8,
+}
+
+impl From<u8> for RegulatorStatus {
+    fn from(raw: u8) -> Self {
+        let code = (RegulatorsDisplayFlags::from_bits_truncate(raw) & RegulatorsDisplayFlags::REG).bits() >> 4;
+        Self {
+            voltage: if code == 0 {
+                None
+            } else {
+                Some(RegulatorVoltage::from_code(code))
+            },
+            raw,
+        }
+    }
+}
+
+/// Decoded Receiver State Display Register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ReceiverState {
+    /// Peak RSSI of the last reception, 4 bit value with 2.15dB per LSB
+    pub rssi: u8,
+    /// Unlatched oscillator ok flag in ISO mode, target activation detector output in NFC mode
+    pub oscillator_ok_or_rf_present: bool,
+    /// Receiver gain reduction steps currently in effect, set by the AGC or Squelch
+    pub gain_reduction_steps: u8,
+    /// Raw Receiver State Display Register value
+    pub raw: u8,
+}
+
+impl ReceiverState {
+    /// RSSI relative to the lowest detectable level (280µVrms on RFI1), in hundredths of a dB
+    pub fn rssi_millibels(&self) -> u16 {
+        self.rssi as u16 * 215
+    }
+}
+
+impl From<u8> for ReceiverState {
+    fn from(raw: u8) -> Self {
+        let flags = ReceiverStateDisplayFlags::from_bits_truncate(raw);
+        Self {
+            rssi: (flags & ReceiverStateDisplayFlags::RSSI).bits() >> 4,
+            oscillator_ok_or_rf_present: flags.contains(ReceiverStateDisplayFlags::OSCOK_RFP),
+            gain_reduction_steps: (flags & ReceiverStateDisplayFlags::GR).bits(),
+            raw,
+        }
+    }
+}
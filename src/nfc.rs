@@ -0,0 +1,57 @@
+//! NFCIP-1 active mode (ISO/IEC 18092) RF collision avoidance support.
+
+/// Initial delay time TIDT before the Initial RF Collision Avoidance, in µs
+pub const INITIAL_DELAY_US: u32 = 302;
+/// Initial guard time TIRFG the field is kept on before the request is sent, in µs
+pub const INITIAL_GUARD_US: u32 = 5110;
+
+/// Result of an NFC transmit command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RfcaOutcome {
+    /// No external field was present, the frame was sent
+    Transmitted,
+    /// An external field was detected during RF collision avoidance, nothing was sent
+    ExternalFieldDetected,
+}
+
+/// Change of the external RF field reported by the Target Activation Detector
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ExternalFieldEvent {
+    /// Another device (reader or phone) switched on its field
+    FieldDetected,
+    /// The external field disappeared
+    FieldLost,
+}
+
+/// Active delay time TADT before the Response RF Collision Avoidance, in µs
+pub const ACTIVE_DELAY_US: u32 = 151;
+/// RF waiting time TRFW, one slot of the randomized response delay, in µs (37.76µs rounded up)
+pub const RF_WAITING_US: u32 = 38;
+/// Active guard time TARFG the field is kept on before the response is sent, in µs
+pub const ACTIVE_GUARD_US: u32 = 84;
+/// Guard time TGAS the field stays on after a request or response was sent, in µs
+pub const GUARD_AFTER_SEND_US: u32 = 65;
+
+/// Response RF Collision Avoidance variant, selects the number `n` of RF waiting time slots
+/// added to the active delay time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ResponseCollisionAvoidance {
+    /// `n` is randomly chosen by the chip in the range 0 to 3
+    Random,
+    /// `n` is 0, used when the response time is deterministic
+    N0,
+}
+
+impl ResponseCollisionAvoidance {
+    /// Worst case duration of the collision avoidance sequence before the response is sent, in µs
+    pub fn max_duration_us(&self) -> u32 {
+        let slots = match self {
+            ResponseCollisionAvoidance::Random => 3,
+            ResponseCollisionAvoidance::N0 => 0,
+        };
+        ACTIVE_DELAY_US + slots * RF_WAITING_US + ACTIVE_GUARD_US
+    }
+}
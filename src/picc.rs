@@ -1,6 +1,8 @@
 //! The PICC (short for Proximity Integrated Circuit Card) is a card or tag
 //! using the ISO 14443A interface, eg Mifare or NTAG203.
 
+use crate::AtqA;
+
 /// Commands that can be send to the PICC.
 ///
 /// The commands used for MIFARE Classic begin with **Mf**
@@ -13,7 +15,7 @@
 /// then use the other commands to read/write/modify the blocks on the sector.
 ///
 /// The read/write commands can also be used for MIFARE Ultralight.
-#[allow(dead_code)]
+#[allow(dead_code, clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Command {
     /// REQuest command, Type A. invites PICCs in state IDLE to go to READY\
@@ -57,9 +59,9 @@ pub enum Command {
 }
 
 /// PICC Type
-#[allow(dead_code)]
-#[derive(Debug)]
-pub enum Type {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CardType {
     Unknown,
     /// PICC compliant with ISO/IEC 14443-4
     Iso14443_4,
@@ -71,20 +73,42 @@ pub enum Type {
     Mifare1k,
     /// MIFARE Classic protocol, 4KB
     Mifare4k,
-    /// MIFARE Ultralight or Ultralight C
+    /// SmartMX with MIFARE Classic 1KB emulation, also compliant with ISO/IEC 14443-4
+    SmartMx1k,
+    /// SmartMX with MIFARE Classic 4KB emulation, also compliant with ISO/IEC 14443-4
+    SmartMx4k,
+    /// MIFARE Ultralight, Ultralight C or NTAG
     MifareUL,
     /// MIFARE Plus
     MifarePlus,
-    /// MIFARE DESFire
+    /// MIFARE DESFire. Not reported by `CardType::from`, see `MifareDesfireOrPlus`
     MifareDesfire,
+    /// MIFARE DESFire or MIFARE Plus in security level 3, which share ATQA and SAK.
+    /// AN10833 tells them apart with GetVersion.
+    MifareDesfireOrPlus,
     /// Only mentioned in NXP AN 10833 MIFARE Type Identification Procedure
     TNP3XXX,
     /// SAK indicates UID is not complete.
     NotComplete,
 }
 
+impl CardType {
+    /// Identifies the PICC from its ATQA and the SAK of the last cascade level
+    ///
+    /// Follows NXP AN10833 MIFARE Type Identification Procedure, the ATQA is used to tell
+    /// DESFire and MIFARE Plus apart from other ISO/IEC 14443-4 PICCs which share the same SAK.
+    /// The result is a guess, the procedure needs GetVersion or RATS for a definite answer.
+    pub fn from(atqa: AtqA, sak: Sak) -> Self {
+        match sak.get_type() {
+            CardType::Iso14443_4 if atqa.bytes == [0x44, 0x03] => CardType::MifareDesfireOrPlus,
+            card_type => card_type,
+        }
+    }
+}
+
 /// Select Acknowledge
-#[derive(Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Sak {
     byte: u8,
 }
@@ -96,23 +120,31 @@ impl From<u8> for Sak {
 }
 
 impl Sak {
-    #[allow(dead_code)]
-    pub fn get_type(&self) -> Type {
+    /// Raw SAK byte
+    pub fn byte(&self) -> u8 {
+        self.byte
+    }
+
+    /// Guesses the PICC type from the SAK alone, see `CardType::from` for a better guess
+    pub fn get_type(&self) -> CardType {
         // https://www.nxp.com/docs/en/application-note/AN10833.pdf
         // 3.2 Coding of Select Acknowledge (SAK)
         // ignore 8-bit (iso14443 starts with LSBit = bit 1)
         // fixes wrong type for manufacturer Infineon (http://nfc-tools.org/index.php?title=ISO14443A)
         match self.byte & 0x7F {
-            0x04 => Type::NotComplete, // UID not complete
-            0x09 => Type::MifareMini,
-            0x08 => Type::Mifare1k,
-            0x18 => Type::Mifare4k,
-            0x00 => Type::MifareUL,
-            0x10 | 0x11 => Type::MifarePlus,
-            0x01 => Type::TNP3XXX,
-            0x20 => Type::Iso14443_4,
-            0x40 => Type::Iso18092,
-            _ => Type::Unknown,
+            0x04 => CardType::NotComplete, // UID not complete
+            0x09 => CardType::MifareMini,
+            0x08 => CardType::Mifare1k,
+            0x18 => CardType::Mifare4k,
+            // SmartMX based PICCs emulating MIFARE Classic, they also answer RATS
+            0x28 => CardType::SmartMx1k,
+            0x38 => CardType::SmartMx4k,
+            0x00 => CardType::MifareUL,
+            0x10 | 0x11 => CardType::MifarePlus,
+            0x01 => CardType::TNP3XXX,
+            0x20 => CardType::Iso14443_4,
+            0x40 => CardType::Iso18092,
+            _ => CardType::Unknown,
         }
     }
 
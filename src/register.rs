@@ -1,40 +1,70 @@
+/// AS3910 register map.
+///
+/// Addresses are 6 bit (up to 0x3F), but the datasheet (rev. 2.3, §8.11) only documents the
+/// configuration (RW) and display (R) registers at 0x00 to 0x17. It describes neither the
+/// other addresses nor any test register, the test mode is only mentioned as entered through
+/// the TEST pin (§8.20), so there are no variants for them.
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
 pub enum Register {
+    /// ISO Mode Definition Register (RW). Selects ISO-14443A/B or NFC mode and the data rates
     ModeDefinition = 0x00,
+    /// Operation Control Register (RW). Enables oscillator/regulators, receiver and transmitter
     OperationControl = 0x01,
 
+    /// Configuration Register 2 (RW). Antenna driving, receiver gain, crystal and MCU_CLK selection
     ConfigurationRegister2 = 0x02,
-    /// SO-14443A and NFC
+    /// Configuration Register 3 (RW), ISO-14443A and NFC. CRC and parity handling, modulation pulse length
     ConfigurationRegister3 = 0x03,
-    /// SO-14443B
+    /// Configuration Register 4 (RW), ISO-14443B. EGT, SOF and EOF definition
     ConfigurationRegister4 = 0x04,
+    /// Configuration Register 5 (RW). AM/PM demodulation, transparent mode modulation and FIFO water levels
     ConfigurationRegister5 = 0x05,
 
+    /// Receiver Configuration Register (RW). AGC, gain reduction and filter selection
     ReceiverConfiguration = 0x06,
+    /// Mask Interrupt Register (RW). A set bit disables the corresponding interrupt source
     MaskInterrupt = 0x07,
+    /// Interrupt Register (R). Content is cleared after read
     Interrupt = 0x08,
+    /// FIFO Status Register (R). Number of unread bytes, overflow and active receive flags
     FIFOStatus = 0x09,
-    /// For ISO-14443A only
+    /// Collision Register (R), ISO-14443A only. Position of the bit collision
     Collision = 0x0A,
+    /// Number of Transmitted Bytes Register 0 (RW). Byte count LSB bits, split byte bits, frame options
     NumberOfTransmittedBytes0 = 0x0B,
+    /// Number of Transmitted Bytes Register 1 (RW). Byte count MSB bits
     NumberOfTransmittedBytes1 = 0x0C,
+    /// A/D Converter Output Register (R). Result of the last A/D conversion
     ADConverterOutput = 0x0D,
+    /// Antenna Calibration Register (R). Result of Calibrate antenna command
     AntennaCalibration = 0x0E,
+    /// External Trim Register (RW). Manual control of the LC trim switches
     ExternalTrim = 0x0F,
 
+    /// Modulation Depth Definition Register (RW). Target AM modulation depth
     ModularDepthDefinition = 0x10,
+    /// Modulation Depth Display Register (R). Result of Calibrate Modulation Depth command
     ModularDepthDisplay = 0x11,
+    /// Antenna Driver AM Modulated Level Definition Register (RW). Driver segments disabled during AM modulated state
     AntennaDriverAMModulatedLevelDefinition = 0x12,
+    /// Antenna Driver Non-Modulated Level Definition Register (RW). Driver segments disabled during non-modulated state
     AntennaDriverNonModulatedLevelDefinition = 0x13,
+    /// NFCIP Field Detection Threshold Register (RW). Target activation and collision avoidance thresholds
     NFCIPFieldDetectionThreshold  = 0x14,
 
+    /// Regulators Display Register (R). Actual regulated voltage
     RegulatorsDisplay  = 0x15,
+    /// Regulated Voltage Definition Register (RW). Automatic or external regulated voltage definition
     RegulatedVoltageDefinition  = 0x16,
+    /// Receiver State Display Register (R). RSSI peak value, oscillator/RF presence and gain reduction
     ReceiverStateDisplay  = 0x17,
 }
 
+/// Number of addressable registers (0x00 to 0x17)
+pub const REGISTER_COUNT: usize = Register::ReceiverStateDisplay as usize + 1;
+
 impl From<Register> for u8 {
     #[inline(always)]
     fn from(variant: Register) -> Self {
@@ -42,10 +72,84 @@ impl From<Register> for u8 {
     }
 }
 
+impl TryFrom<u8> for Register {
+    type Error = u8;
+
+    /// Converts a register address, returns the address back when it isn't a known register
+    fn try_from(address: u8) -> Result<Self, Self::Error> {
+        match address {
+            0x00 => Ok(Register::ModeDefinition),
+            0x01 => Ok(Register::OperationControl),
+            0x02 => Ok(Register::ConfigurationRegister2),
+            0x03 => Ok(Register::ConfigurationRegister3),
+            0x04 => Ok(Register::ConfigurationRegister4),
+            0x05 => Ok(Register::ConfigurationRegister5),
+            0x06 => Ok(Register::ReceiverConfiguration),
+            0x07 => Ok(Register::MaskInterrupt),
+            0x08 => Ok(Register::Interrupt),
+            0x09 => Ok(Register::FIFOStatus),
+            0x0A => Ok(Register::Collision),
+            0x0B => Ok(Register::NumberOfTransmittedBytes0),
+            0x0C => Ok(Register::NumberOfTransmittedBytes1),
+            0x0D => Ok(Register::ADConverterOutput),
+            0x0E => Ok(Register::AntennaCalibration),
+            0x0F => Ok(Register::ExternalTrim),
+            0x10 => Ok(Register::ModularDepthDefinition),
+            0x11 => Ok(Register::ModularDepthDisplay),
+            0x12 => Ok(Register::AntennaDriverAMModulatedLevelDefinition),
+            0x13 => Ok(Register::AntennaDriverNonModulatedLevelDefinition),
+            0x14 => Ok(Register::NFCIPFieldDetectionThreshold),
+            0x15 => Ok(Register::RegulatorsDisplay),
+            0x16 => Ok(Register::RegulatedVoltageDefinition),
+            0x17 => Ok(Register::ReceiverStateDisplay),
+            _ => Err(address),
+        }
+    }
+}
+
 const R: u8 = 1 << 6;
 const W: u8 = 0 << 6;
 
 impl Register {
+    /// Display registers are read only, writes to them are ignored by the chip
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Register::Interrupt
+                | Register::FIFOStatus
+                | Register::Collision
+                | Register::ADConverterOutput
+                | Register::AntennaCalibration
+                | Register::ModularDepthDisplay
+                | Register::RegulatorsDisplay
+                | Register::ReceiverStateDisplay
+        )
+    }
+
+    /// Bits of the register which are implemented and can be written,
+    /// unused bits and read only registers read back as 0
+    pub fn write_mask(&self) -> u8 {
+        match self {
+            Register::ModeDefinition => 0b1111_1111,
+            Register::OperationControl => 0b1111_1000,
+            Register::ConfigurationRegister2 => 0b1111_1110,
+            Register::ConfigurationRegister3 => 0b1111_1100,
+            Register::ConfigurationRegister4 => 0b1111_1110,
+            Register::ConfigurationRegister5 => 0b1100_0110,
+            Register::ReceiverConfiguration => 0b1111_1111,
+            Register::MaskInterrupt => 0b1111_1111,
+            Register::NumberOfTransmittedBytes0 => 0b1111_1011,
+            Register::NumberOfTransmittedBytes1 => 0b1111_1111,
+            Register::ExternalTrim => 0b1111_1000,
+            Register::ModularDepthDefinition => 0b1111_1110,
+            Register::AntennaDriverAMModulatedLevelDefinition => 0b1111_1111,
+            Register::AntennaDriverNonModulatedLevelDefinition => 0b1111_1111,
+            Register::NFCIPFieldDetectionThreshold => 0b1111_1111,
+            Register::RegulatedVoltageDefinition => 0b1111_1000,
+            _ => 0,
+        }
+    }
+
     pub fn read_address(&self) -> u8 {
         (*self as u8) | R
     }
@@ -55,6 +159,47 @@ impl Register {
     }
 }
 
+/// Snapshot of the register map, see `AS3910::dump_registers`.
+///
+/// Interrupt register is not included since reading it clears pending interrupts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RegisterDump {
+    pub mode_definition: u8,
+    pub operation_control: u8,
+    pub configuration_register2: u8,
+    pub configuration_register3: u8,
+    pub configuration_register4: u8,
+    pub configuration_register5: u8,
+    pub receiver_configuration: u8,
+    pub mask_interrupt: u8,
+    pub fifo_status: u8,
+    pub collision: u8,
+    pub number_of_transmitted_bytes0: u8,
+    pub number_of_transmitted_bytes1: u8,
+    pub ad_converter_output: u8,
+    pub antenna_calibration: u8,
+    pub external_trim: u8,
+    pub modular_depth_definition: u8,
+    pub modular_depth_display: u8,
+    pub antenna_driver_am_modulated_level_definition: u8,
+    pub antenna_driver_non_modulated_level_definition: u8,
+    pub nfcip_field_detection_threshold: u8,
+    pub regulators_display: u8,
+    pub regulated_voltage_definition: u8,
+    pub receiver_state_display: u8,
+    /// Last written value of every register by address, see `AS3910::cached`.
+    /// A differing live value shows a register the chip changed itself or a lost write
+    pub cached: [Option<u8>; REGISTER_COUNT],
+}
+
+impl RegisterDump {
+    /// Cached value of `reg` next to the live one in this dump
+    pub fn cached(&self, reg: Register) -> Option<u8> {
+        self.cached.get(reg as usize).copied().flatten()
+    }
+}
+
 bitflags! {
     pub struct InterruptFlags: u8 {
         const BIT_COLLISION = 0b0000_0001;
@@ -69,3 +214,332 @@ bitflags! {
         const ALL = 0b1111_1111;
     }
 }
+
+static INTERRUPT_NAMES: [(InterruptFlags, &str); 8] = [
+    (InterruptFlags::BIT_COLLISION, "BIT_COLLISION"),
+    (InterruptFlags::CRC_ERROR, "CRC_ERROR"),
+    (InterruptFlags::RECEIVE_DATA_CODING_ERROR, "RECEIVE_DATA_CODING_ERROR"),
+    (InterruptFlags::END_OF_TRANSMISSION, "END_OF_TRANSMISSION"),
+    (InterruptFlags::END_OF_RECEIVE, "END_OF_RECEIVE"),
+    (InterruptFlags::FIFO_WATER_LEVEL, "FIFO_WATER_LEVEL"),
+    (InterruptFlags::NFC_EVENT, "NFC_EVENT"),
+    (InterruptFlags::OSCILLATOR_FREQUENCY_STABLE, "OSCILLATOR_FREQUENCY_STABLE"),
+];
+
+impl InterruptFlags {
+    /// Iterates over the individual flags that are set, from LSB to MSB
+    pub fn iter(&self) -> impl Iterator<Item = InterruptFlags> {
+        let flags = *self;
+        INTERRUPT_NAMES
+            .iter()
+            .filter(move |(flag, _)| flags.contains(*flag))
+            .map(|(flag, _)| *flag)
+    }
+
+    /// Name of a single flag, `None` for empty or combined flags
+    pub fn name(&self) -> Option<&'static str> {
+        INTERRUPT_NAMES
+            .iter()
+            .find(|(flag, _)| flag == self)
+            .map(|(_, name)| *name)
+    }
+}
+
+/// A single interrupt source, decoded from the Interrupt Register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Event {
+    /// Bit collision detected during anticollision, position is in the Collision Register
+    BitCollision,
+    /// CRC of the received frame didn't match
+    CrcError,
+    /// Framing, parity or subcarrier error in the received data
+    ReceiveDataCodingError,
+    /// Transmission finished, in NFC mode the own field is switched off
+    EndOfTransmission,
+    /// Reception finished, all data is in the FIFO
+    EndOfReceive,
+    /// FIFO fill level crossed the water level
+    FifoWaterLevel,
+    /// External field detected (or disappeared) in NFC mode
+    NfcEvent,
+    /// Oscillator amplitude reached its operating level
+    OscillatorFrequencyStable,
+}
+
+impl Event {
+    pub fn flag(&self) -> InterruptFlags {
+        match self {
+            Event::BitCollision => InterruptFlags::BIT_COLLISION,
+            Event::CrcError => InterruptFlags::CRC_ERROR,
+            Event::ReceiveDataCodingError => InterruptFlags::RECEIVE_DATA_CODING_ERROR,
+            Event::EndOfTransmission => InterruptFlags::END_OF_TRANSMISSION,
+            Event::EndOfReceive => InterruptFlags::END_OF_RECEIVE,
+            Event::FifoWaterLevel => InterruptFlags::FIFO_WATER_LEVEL,
+            Event::NfcEvent => InterruptFlags::NFC_EVENT,
+            Event::OscillatorFrequencyStable => InterruptFlags::OSCILLATOR_FREQUENCY_STABLE,
+        }
+    }
+}
+
+static EVENTS: [Event; 8] = [
+    Event::BitCollision,
+    Event::CrcError,
+    Event::ReceiveDataCodingError,
+    Event::EndOfTransmission,
+    Event::EndOfReceive,
+    Event::FifoWaterLevel,
+    Event::NfcEvent,
+    Event::OscillatorFrequencyStable,
+];
+
+impl InterruptFlags {
+    /// Decodes every set flag into an `Event`, from LSB to MSB
+    pub fn events(&self) -> impl Iterator<Item = Event> {
+        let flags = *self;
+        EVENTS.iter().copied().filter(move |event| flags.contains(event.flag()))
+    }
+}
+
+impl core::fmt::Display for InterruptFlags {
+    /// Lists set flags by name, e.g. `END_OF_TRANSMISSION | END_OF_RECEIVE`
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.is_empty() {
+            return f.write_str("NONE");
+        }
+        for (i, flag) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" | ")?;
+            }
+            f.write_str(flag.name().unwrap_or("?"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for InterruptFlags {
+    fn format(&self, f: defmt::Formatter) {
+        if self.is_empty() {
+            defmt::write!(f, "NONE");
+            return;
+        }
+        for (i, flag) in self.iter().enumerate() {
+            if i > 0 {
+                defmt::write!(f, " | ");
+            }
+            defmt::write!(f, "{=str}", flag.name().unwrap_or("?"));
+        }
+    }
+}
+
+bitflags! {
+    /// ISO Mode Definition Register (0x00)
+    pub struct ModeDefinitionFlags: u8 {
+        /// NFCIP-1 106 kbps active communication mode, otherwise ISO-14443
+        const NFC = 0b1000_0000;
+        /// ISO-14443B, otherwise ISO-14443A. Applicable in case `NFC` is not set
+        const ISO14443B = 0b0100_0000;
+        /// Transmit data rate (106, 212, 424 or 848 kb/s)
+        const TX_RATE = 0b0011_1000;
+        /// Receive data rate (106, 212, 424 or 848 kb/s)
+        const RX_RATE = 0b0000_0111;
+    }
+}
+
+bitflags! {
+    /// Operation Control Register (0x01)
+    pub struct OperationControlFlags: u8 {
+        /// Enables oscillator and regulator (Ready mode)
+        const EN = 0b1000_0000;
+        /// Enables receiver operation
+        const RX_EN = 0b0100_0000;
+        /// Low power receiver operation
+        const RX_LP = 0b0010_0000;
+        /// Enables RF output
+        const TX_EN = 0b0001_0000;
+        /// Enables Initial NFC Target mode
+        const NFC_T = 0b0000_1000;
+    }
+}
+
+bitflags! {
+    /// Configuration Register 2 (0x02)
+    pub struct Configuration2Flags: u8 {
+        /// Only RFO1 driver will be used (single ended driving)
+        const SING = 0b1000_0000;
+        /// Input applied to RFI1 is RF envelope
+        const ENVI = 0b0100_0000;
+        /// Reduces the gain for 11 dB in first stage after peak detector
+        const TF2 = 0b0010_0000;
+        /// Reduces the gain for 6 dB in first stage after peak detector
+        const TF1 = 0b0001_0000;
+        /// 27.12MHz crystal, otherwise 13.56MHz
+        const OSC = 0b0000_1000;
+        /// Clock frequency on MCU_CLK output
+        const OUT_CL = 0b0000_0110;
+    }
+}
+
+bitflags! {
+    /// Configuration Register 3 (0x03), ISO-14443A and NFC
+    pub struct Configuration3Flags: u8 {
+        /// Receive without CRC
+        const CRC_RX = 0b1000_0000;
+        /// No byte parity checking
+        const NO_PAR = 0b0100_0000;
+        /// Modulation pulse reduction in number of 13.56 MHz clock periods
+        const P_LEN = 0b0011_1100;
+    }
+}
+
+bitflags! {
+    /// Configuration Register 4 (0x04), ISO-14443B
+    pub struct Configuration4Flags: u8 {
+        /// EGT time defined in number of etu
+        const EGT_LEN = 0b1110_0000;
+        /// SOF, 11 etu with logic 0, otherwise 10 etu
+        const SOF_0 = 0b0001_0000;
+        /// SOF, 3 etu with logic 1, otherwise 2 etu
+        const SOF_1 = 0b0000_1000;
+        /// EOF, 11 etu with logic 0, otherwise 10 etu
+        const EOF = 0b0000_0100;
+        /// EGT after each character, otherwise no EGT after last character
+        const EGT = 0b0000_0010;
+    }
+}
+
+bitflags! {
+    /// Configuration Register 5 (0x05)
+    pub struct Configuration5Flags: u8 {
+        /// PM demodulation, otherwise AM demodulation
+        const PMD = 0b1000_0000;
+        /// AM modulation in Transparent mode, otherwise OOK
+        const AM = 0b0100_0000;
+        /// FIFO water level for receive is 24, otherwise 28
+        const FIFO_LR = 0b0000_0100;
+        /// FIFO water level for transmit is 8, otherwise 4
+        const FIFO_LT = 0b0000_0010;
+    }
+}
+
+bitflags! {
+    /// Receiver Configuration Register (0x06)
+    pub struct ReceiverConfigurationFlags: u8 {
+        /// AGC is enabled
+        const AGC_EN = 0b1000_0000;
+        /// AGC operates on first eight subcarrier pulses, otherwise during complete receive period
+        const AGC_M = 0b0100_0000;
+        /// Gain reduction in 3dB steps
+        const RG = 0b0011_1000;
+        /// Filter selection
+        const FS = 0b0000_0111;
+    }
+}
+
+bitflags! {
+    /// FIFO Status Register (0x09)
+    pub struct FifoStatusFlags: u8 {
+        /// Number of bytes in the FIFO which were not read out
+        const FIFO_B = 0b1111_1100;
+        /// FIFO overflow
+        const FIFO_OVR = 0b0000_0010;
+        /// Active receive
+        const RX_ACT = 0b0000_0001;
+    }
+}
+
+bitflags! {
+    /// Collision Register (0x0A), ISO-14443A only
+    pub struct CollisionFlags: u8 {
+        /// Number of full bytes before the bit collision happened
+        const C_BYTE = 0b1111_0000;
+        /// Number of bits before the collision in the byte where the collision happened
+        const C_BIT = 0b0000_1110;
+    }
+}
+
+bitflags! {
+    /// Number of Transmitted Bytes Register 0 (0x0B)
+    pub struct NumberOfTransmittedBytes0Flags: u8 {
+        /// Number of bytes to be transmitted, LSB bits
+        const NTX = 0b1100_0000;
+        /// Number of bits in the split byte
+        const NBTX = 0b0011_1000;
+        /// 4bit response frame (Mifare Ultralight ACK/NACK)
+        const FRM4 = 0b0000_0010;
+        /// ISO-14443A bit oriented anticollision frame
+        const ANTCL = 0b0000_0001;
+    }
+}
+
+bitflags! {
+    /// Antenna Calibration Register (0x0E)
+    pub struct AntennaCalibrationFlags: u8 {
+        /// Trim switches state found by Calibrate antenna command
+        const TRI = 0b1111_0000;
+        /// Antenna calibration error
+        const TRI_ERR = 0b0000_1000;
+    }
+}
+
+bitflags! {
+    /// External Trim Register (0x0F)
+    pub struct ExternalTrimFlags: u8 {
+        /// LC trim switches are defined by `TRE` bits, otherwise by Calibrate antenna result
+        const TRIM_S = 0b1000_0000;
+        /// Externally defined trim switches state
+        const TRE = 0b0111_1000;
+    }
+}
+
+bitflags! {
+    /// Modulation Depth Definition Register (0x10)
+    pub struct ModulationDepthDefinitionFlags: u8 {
+        /// AM modulated level is defined by Antenna Driver AM Modulated Level Definition Register,
+        /// otherwise by `MOD` bits and Calibrate Modulation Depth command
+        const AM_S = 0b1000_0000;
+        /// Target AM modulation depth
+        const MOD = 0b0111_1110;
+    }
+}
+
+bitflags! {
+    /// NFCIP Field Detection Threshold Register (0x14)
+    pub struct NfcipFieldDetectionThresholdFlags: u8 {
+        /// Target activation level
+        const TRG_L = 0b1111_0000;
+        /// Collision avoidance threshold
+        const RFE_T = 0b0000_1111;
+    }
+}
+
+bitflags! {
+    /// Regulators Display Register (0x15)
+    pub struct RegulatorsDisplayFlags: u8 {
+        /// Actual regulated voltage
+        const REG = 0b1111_0000;
+    }
+}
+
+bitflags! {
+    /// Regulated Voltage Definition Register (0x16)
+    pub struct RegulatedVoltageDefinitionFlags: u8 {
+        /// Regulated voltages are defined by `REGE` bits, otherwise by Adjust regulators command
+        const REG_S = 0b1000_0000;
+        /// External definition of regulated voltage
+        const REGE = 0b0111_1000;
+    }
+}
+
+bitflags! {
+    /// Receiver State Display Register (0x17)
+    pub struct ReceiverStateDisplayFlags: u8 {
+        /// Peak value of RSSI measurement
+        const RSSI = 0b1111_0000;
+        /// Unlatched osc_ok flag (ISO mode) or target activation detector output (NFC mode)
+        const OSCOK_RFP = 0b0000_1000;
+        /// Receiver gain reduction currently in effect
+        const GR = 0b0000_0111;
+    }
+}
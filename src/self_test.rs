@@ -0,0 +1,56 @@
+//! End-of-line production self-test of an AS3910 reader.
+
+use crate::calibration::{AntennaResonance, ModulationDepthResult};
+use crate::measurement::{Millivolts, RegulatorStatus};
+
+/// Minimum carrier amplitude on the RFI inputs for a working antenna, in mVpp
+pub const MIN_RF_AMPLITUDE: Millivolts = Millivolts(1000);
+
+/// Outcome of `AS3910::self_test`, each check can be inspected on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfTestReport {
+    /// Check antenna resonance result
+    pub resonance: AntennaResonance,
+    /// Calibrate modulation depth result, `None` when manual modulation depth is configured
+    pub modulation_depth: Option<ModulationDepthResult>,
+    /// Regulated voltage after Adjust regulators
+    pub regulators: RegulatorStatus,
+    /// Carrier amplitude measured with Measure RF
+    pub rf_amplitude: Millivolts,
+    /// Test patterns written to a scratch register were read back unchanged
+    pub registers_ok: bool,
+}
+
+impl SelfTestReport {
+    /// Antenna LC tank is within `calibration::RESONANCE_TOLERANCE` of resonance
+    pub fn resonance_ok(&self) -> bool {
+        !self.resonance.needs_recalibration()
+    }
+
+    /// Modulation depth calibration did not saturate with all or no driver segments disabled
+    pub fn modulation_depth_ok(&self) -> bool {
+        match self.modulation_depth {
+            Some(result) => result.disabled_segments != 0 && result.disabled_segments != 0xFF,
+            None => true,
+        }
+    }
+
+    /// Regulators are operating
+    pub fn regulators_ok(&self) -> bool {
+        self.regulators.voltage.is_some()
+    }
+
+    /// Carrier amplitude reaches `MIN_RF_AMPLITUDE`
+    pub fn rf_amplitude_ok(&self) -> bool {
+        self.rf_amplitude >= MIN_RF_AMPLITUDE
+    }
+
+    /// All checks passed
+    pub fn passed(&self) -> bool {
+        self.registers_ok
+            && self.resonance_ok()
+            && self.modulation_depth_ok()
+            && self.regulators_ok()
+            && self.rf_amplitude_ok()
+    }
+}
@@ -0,0 +1,19 @@
+//! Link health counters, collected when `Config::collect_stats` is set.
+
+/// Counters since initialization or the last `AS3910::reset_stats`. All counters saturate.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Stats {
+    /// Frames sent with one of the transmit commands, including REQA and WUPA
+    pub frames_sent: u32,
+    /// Responses with a CRC error
+    pub crc_errors: u32,
+    /// Responses with a framing, parity or coding error
+    pub framing_errors: u32,
+    /// Responses with a bit collision, expected during anticollision with several PICCs
+    pub collisions: u32,
+    /// Frames the PICC didn't answer
+    pub timeouts: u32,
+    /// Runs of `AS3910::recover`
+    pub recoveries: u32,
+}
@@ -0,0 +1,70 @@
+//! ISO-14443A frame timing used to derive response timeouts.
+
+/// Upper bound of the time needed to transmit a frame of `len` bytes plus CRC,
+/// at 106kbps with 9 bits per byte including parity
+pub fn frame_duration_us(len: usize) -> u32 {
+    (len as u32 + 2) * 9 * 944 / 100
+}
+
+/// Frame delay time between the end of a command and the start of the PICC response,
+/// 1236/fc for commands ending with a logic 0 (the longer of the two), in µs
+pub const FRAME_DELAY_US: u32 = 92;
+
+/// Shortest frame delay time, 1172/fc for commands ending with a logic 1, in µs
+pub const MIN_FRAME_DELAY_US: u32 = 86;
+
+/// Longest receive mask after the end of transmission. The AGC starts 20 µs after the receiver
+/// is enabled, so it has to be unmasked that long before the earliest response
+pub const MAX_RECEIVE_MASK_WINDOW_US: u16 = MIN_FRAME_DELAY_US as u16 - 20;
+
+/// Additional frame waiting time the PCD allows, ΔFWT = 49152/fc, in µs
+pub const DELTA_FWT_US: u32 = 3625;
+
+/// Activation frame waiting time the PICC has to send the ATS in, 71680/fc, in µs
+pub const ACTIVATION_FWT_US: u32 = 5287;
+
+/// Frame waiting time for the response to S(DESELECT), 65536/fc, in µs
+pub const DESELECT_FWT_US: u32 = 4833;
+
+/// Shortest timeout for a response. Interrupts are polled once per millisecond and the SPI
+/// accesses around the wait add latency on slow hosts, so a 1 ms wait may end before the
+/// response arrived
+pub const MIN_RESPONSE_TIMEOUT_MS: u16 = 5;
+
+/// Frame waiting time integer used until the ATS was received
+pub const DEFAULT_FWI: u8 = 4;
+
+/// Highest frame waiting time integer, S(WTX) can't extend the frame waiting time beyond it
+pub const MAX_FWI: u8 = 14;
+
+/// Frame waiting time FWT = 256 * 16 / fc * 2^FWI, in µs.
+/// The RFU value 15 is interpreted as `DEFAULT_FWI`.
+pub fn fwt_us(fwi: u8) -> u32 {
+    let fwi = if fwi > MAX_FWI { DEFAULT_FWI } else { fwi };
+    (((4096u64 * 100) << fwi) / 1356) as u32
+}
+
+/// Start-up frame guard time the PICC needs after the ATS including its tolerance,
+/// SFGT + ΔSFGT = (256 * 16 + 384) / fc * 2^SFGI, in µs. 0 and the RFU value 15 need no guard time.
+pub fn sfgt_us(sfgi: u8) -> u32 {
+    match sfgi {
+        1..=14 => ((((4096u64 + 384) * 100) << sfgi) / 1356) as u32,
+        _ => 0,
+    }
+}
+
+/// Timeout for an interrupt wait of `us`, rounded up to whole milliseconds
+pub fn timeout_ms(us: u32) -> u16 {
+    us.div_ceil(1000).min(u16::MAX as u32) as u16
+}
+
+/// Time to wait for an ISO-14443-3 response of `len` bytes (FDT plus the frame itself), in ms,
+/// at least `MIN_RESPONSE_TIMEOUT_MS`
+pub fn response_timeout_ms(len: usize) -> u16 {
+    timeout_ms(FRAME_DELAY_US + frame_duration_us(len)).max(MIN_RESPONSE_TIMEOUT_MS)
+}
+
+/// Time to wait for an ISO-DEP response with the frame waiting time of `fwi`, in ms
+pub fn iso_dep_timeout_ms(fwi: u8) -> u16 {
+    timeout_ms(fwt_us(fwi) + DELTA_FWT_US)
+}